@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _};
+
+use crate::spk::{self, FileInfo, OpenError, ReadError};
+
+impl spk::SPKFile<'static> {
+    /// Opens the archive at `path` like [`SPKFile::open`](spk::SPKFile::open),
+    /// parsing it on a blocking thread.
+    ///
+    /// Archives in a single uncompressed .spk file are opened so that
+    /// [`SPKFile::read_async`](spk::SPKFile::read_async) reads from the file
+    /// asynchronously. Other archives are held in memory once opened.
+    pub async fn open_async(path: &Path) -> Result<Self, OpenError> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            match path.extension().and_then(std::ffi::OsStr::to_str) {
+                Some("spk") if !spk::is_gzip(&path)? => Self::with_parallel_handles(&path),
+                _ => Self::open(&path),
+            }
+        })
+        .await
+        .map_err(|error| OpenError::IOError(std::io::Error::other(error)))?
+    }
+}
+
+impl spk::SPKFile<'_> {
+    /// Reads the stored contents of `file` like [`SPKFile::read`](spk::SPKFile::read),
+    /// without blocking.
+    ///
+    /// Each read opens its own handle to the archive, so concurrent reads
+    /// don't contend with each other. This is only possible for archives
+    /// opened from a single file, such as by
+    /// [`SPKFile::open_async`](spk::SPKFile::open_async). Other archives are
+    /// read synchronously, which doesn't block for long as they are held in
+    /// memory.
+    #[allow(clippy::cast_possible_truncation)]
+    pub async fn read_async(&self, file: &FileInfo) -> Result<Vec<u8>, ReadError> {
+        let Some(path) = &self.path else {
+            return self.read(file);
+        };
+
+        self.check_bounds(file.offset, file.data_size)?;
+        let mut archive = tokio::fs::File::open(path).await?;
+        archive.seek(std::io::SeekFrom::Start(file.offset)).await?;
+        let mut buf = vec![0; file.data_size as usize];
+        archive.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+}
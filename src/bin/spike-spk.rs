@@ -7,7 +7,7 @@ use clap::Parser as _;
 
 /// Extract or verify a Stern Pinball software update package
 ///
-/// Update files can be provided as the path to a single .spk file,
+/// Update files can be provided as the path to a single .spk or .spk.gz file,
 /// the path to a directory containing the split update files (.spk.OOX.00{1,2,...}),
 /// or the path to the first of the spilt update files (.spk.OON.000).
 #[derive(Debug, clap::Parser)]
@@ -42,7 +42,7 @@ impl Command for Commands {
 struct VerifyCommand {
     /// The path to the SPK file to verify.
     ///
-    /// The path can be the path to a single .spk file, the path to a directory
+    /// The path can be the path to a single .spk or .spk.gz file, the path to a directory
     /// containing the split update files (.spk.OOX.00{1,2,...}),
     /// or the path to the first of the spilt update files (.spk.OON.000).
     path: PathBuf,
@@ -59,7 +59,7 @@ impl Command for VerifyCommand {
 struct ExtractCommand {
     /// The path to the SPK file to extract.
     ///
-    /// The path can be the path to a single .spk file, the path to a directory
+    /// The path can be the path to a single .spk or .spk.gz file, the path to a directory
     /// containing the split update files (.spk.OOX.00{1,2,...}),
     /// or the path to the first of the spilt update files (.spk.OON.000).
     path: PathBuf,
@@ -1,5 +1,6 @@
 use binrw::{BinRead, FilePtr32, FilePtr64, NullString, binread};
 use md5::digest::generic_array::GenericArray;
+use thiserror::Error;
 
 #[derive(BinRead, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[br(repr(u8))]
@@ -10,7 +11,9 @@ pub enum PackageType {
 }
 
 impl PackageType {
-    pub(crate) fn path_prefix(&self) -> &str {
+    /// The directory that packages of this type are installed under.
+    #[must_use]
+    pub fn path_prefix(&self) -> &str {
         if self == &PackageType::Game {
             "/games/"
         } else {
@@ -19,6 +22,47 @@ impl PackageType {
     }
 }
 
+/// The kinds of chunk an archive is made up of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChunkKind {
+    SPKS,
+    SPK0,
+    SIDX,
+    SZ64,
+    STRS,
+    FINF,
+    FI64,
+    FEND,
+    SDAT,
+}
+
+impl std::fmt::Display for PackageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PackageType::Spike1 => "spike1",
+            PackageType::Spike2 => "spike2",
+            PackageType::Game => "game",
+        })
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown package type: {0}")]
+pub struct UnknownPackageType(pub String);
+
+impl std::str::FromStr for PackageType {
+    type Err = UnknownPackageType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spike1" => Ok(PackageType::Spike1),
+            "spike2" => Ok(PackageType::Spike2),
+            "game" => Ok(PackageType::Game),
+            _ => Err(UnknownPackageType(s.to_string())),
+        }
+    }
+}
+
 #[derive(BinRead, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum ByteLen {
     #[br(magic = 0xffff_ffffu32)]
@@ -41,6 +85,16 @@ impl ByteLen {
             ByteLen::Old(_) => 8,
         }
     }
+
+    /// The size of the smallest header that can store `byte_len`.
+    pub(crate) fn smallest_header_size(byte_len: u64) -> u64 {
+        // A 32-bit length of `ffff ffff` would be read as the start of a 64-bit one.
+        if byte_len < u64::from(u32::MAX) {
+            8
+        } else {
+            16
+        }
+    }
 }
 
 #[derive(BinRead, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -50,6 +104,16 @@ pub(crate) struct SPKS {
     pub chunk_count: u32,
 }
 
+impl SPKS {
+    pub(crate) fn byte_len(&self) -> u64 {
+        self.byte_length.byte_len()
+    }
+
+    pub(crate) fn offset_to_next(&self) -> u64 {
+        self.byte_length.header_size() + self.byte_length.byte_len()
+    }
+}
+
 #[derive(BinRead, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[br(magic = b"SPK0")]
 pub(crate) struct SPK0 {
@@ -57,6 +121,10 @@ pub(crate) struct SPK0 {
 }
 
 impl SPK0 {
+    pub(crate) fn byte_len(&self) -> u64 {
+        self.byte_len.byte_len()
+    }
+
     pub(crate) fn offset_to_next(&self) -> u64 {
         self.byte_len.header_size() + self.byte_len.byte_len()
     }
@@ -75,13 +143,22 @@ pub(crate) struct SIDX {
     pub minor_version: u8,
     pub patch_version: u8,
     pub package_type: PackageType,
-    unknown_b: [u8; 0xc],
+    // The purpose of these bytes is unknown. Neither they nor any other field in
+    // the format have been identified as a timestamp, so
+    // `SPKFile::extract_package_with_times` leaves extracted files with the
+    // modification time at which they were written.
+    pub unknown_b: [u8; 0xc],
+}
+
+impl SIDX {
+    /// The size of a `SIDX` chunk, including its 8 byte header.
+    pub(crate) const LEN: u64 = 8 + 0x1d + 3 + 4 + 0xc;
 }
 
 #[derive(BinRead, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[br(magic = b"STRS")]
 pub(crate) struct STRS {
-    byte_len: u32,
+    pub byte_len: u32,
     #[br(count(byte_len))]
     pub string_data: Vec<u8>,
 }
@@ -98,13 +175,13 @@ impl std::fmt::Debug for STRS {
 #[binread]
 #[derive(Clone, PartialEq, Eq)]
 #[br(magic = b"FINF", import(strs_offset: u64))]
-pub(crate) struct FINF {
+pub struct FINF {
     byte_len: u32,
     #[br(offset(strs_offset), parse_with = FilePtr32::parse, restore_position)]
     filename: NullString,
 
-    #[br(temp)]
-    _filename: u32,
+    // The offset of the file name within the STRS string data.
+    name_offset: u32,
 
     file_size: u32,
 
@@ -114,10 +191,17 @@ pub(crate) struct FINF {
 
     mode: u16,
 
-    #[br(pad_before(3))]
+    // Padding is expected to be zero, but is kept so strict parsing can check.
+    mode_padding: [u8; 3],
     data_hmac: [u8; 20],
-    #[br(pad_after(3))]
     data_md5: [u8; 16],
+    trailing_padding: [u8; 3],
+}
+
+impl FINF {
+    /// The total on-disk size of a `FINF` chunk: the 8 byte chunk header, four
+    /// 32-bit fields, the mode, both digests, and 6 bytes of padding.
+    pub const HEADER_LEN: u64 = 68;
 }
 
 impl std::fmt::Debug for FINF {
@@ -125,10 +209,12 @@ impl std::fmt::Debug for FINF {
         f.debug_struct("FINF")
             .field("byte_len", &self.byte_len)
             .field("filename", &self.filename)
+            .field("name_offset", &self.name_offset)
             .field("file_size", &self.file_size)
             .field("data_offset", &self.data_offset)
             .field("data_size", &self.data_size)
             .field("mode", &self.mode)
+            .field("mode_padding", &self.mode_padding)
             .field(
                 "data_hmac",
                 &format_args!("{:02x}", GenericArray::from(self.data_hmac)),
@@ -137,6 +223,7 @@ impl std::fmt::Debug for FINF {
                 "data_md5",
                 &format_args!("{:02x}", GenericArray::from(self.data_md5)),
             )
+            .field("trailing_padding", &self.trailing_padding)
             .finish()
     }
 }
@@ -144,27 +231,34 @@ impl std::fmt::Debug for FINF {
 #[binread]
 #[derive(Clone, PartialEq, Eq)]
 #[br(magic = b"FI64", import(strs_offset: u64))]
-pub(crate) struct FI64 {
+pub struct FI64 {
     byte_len: u32,
 
     #[br(offset(strs_offset), parse_with = FilePtr64::parse, restore_position)]
-    pub filename: NullString,
+    pub(crate) filename: NullString,
 
-    #[br(temp)]
-    _filename: u64,
+    // The offset of the file name within the STRS string data.
+    pub(crate) name_offset: u64,
 
-    pub file_size: u64,
+    pub(crate) file_size: u64,
 
     // Relative to SDAT.
-    pub data_offset: u64,
-    pub data_size: u64,
+    pub(crate) data_offset: u64,
+    pub(crate) data_size: u64,
 
-    pub mode: u16,
+    pub(crate) mode: u16,
 
-    #[br(pad_before(3))]
-    pub data_hmac: [u8; 20],
-    #[br(pad_after(7))]
-    pub data_md5: [u8; 16],
+    // Padding is expected to be zero, but is kept so strict parsing can check.
+    pub(crate) mode_padding: [u8; 3],
+    pub(crate) data_hmac: [u8; 20],
+    pub(crate) data_md5: [u8; 16],
+    pub(crate) trailing_padding: [u8; 7],
+}
+
+impl FI64 {
+    /// The total on-disk size of a `FI64` chunk: the 8 byte chunk header, four
+    /// 64-bit fields, the mode, both digests, and 10 bytes of padding.
+    pub const HEADER_LEN: u64 = 88;
 }
 
 impl std::fmt::Debug for FI64 {
@@ -172,10 +266,12 @@ impl std::fmt::Debug for FI64 {
         f.debug_struct("FINF")
             .field("byte_len", &self.byte_len)
             .field("filename", &self.filename)
+            .field("name_offset", &self.name_offset)
             .field("file_size", &self.file_size)
             .field("data_offset", &self.data_offset)
             .field("data_size", &self.data_size)
             .field("mode", &self.mode)
+            .field("mode_padding", &self.mode_padding)
             .field(
                 "data_hmac",
                 &format_args!("{:02x}", GenericArray::from(self.data_hmac)),
@@ -184,6 +280,7 @@ impl std::fmt::Debug for FI64 {
                 "data_md5",
                 &format_args!("{:02x}", GenericArray::from(self.data_md5)),
             )
+            .field("trailing_padding", &self.trailing_padding)
             .finish()
     }
 }
@@ -195,6 +292,11 @@ pub(crate) struct FEND {
     byte_len: u32,
 }
 
+impl FEND {
+    /// The size of a `FEND` chunk, which consists only of its header.
+    pub(crate) const LEN: u64 = 8;
+}
+
 #[derive(BinRead, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[br(magic = b"SDAT")]
 pub(crate) struct SDAT {
@@ -202,7 +304,6 @@ pub(crate) struct SDAT {
 }
 
 impl SDAT {
-    #[allow(unused)]
     pub(crate) fn byte_len(&self) -> u64 {
         self.byte_len.byte_len()
     }
@@ -215,8 +316,14 @@ impl SDAT {
 #[derive(BinRead, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[br(magic = b"SZ64")]
 pub(crate) struct SZ64 {
-    byte_len: u32,
-    unknown: u64,
+    pub byte_len: u32,
+    // Not known for certain, but most likely the uncompressed size of the package.
+    pub uncompressed_size: u64,
+}
+
+impl SZ64 {
+    /// The size of a `SZ64` chunk, including its 8 byte header.
+    pub(crate) const LEN: u64 = 16;
 }
 
 #[derive(BinRead, Debug, Clone, PartialEq, Eq)]
@@ -227,18 +334,54 @@ pub(crate) enum FileInfo {
     FEND(FEND),
 }
 
+impl FileInfo {
+    pub(crate) fn kind(&self) -> ChunkKind {
+        match self {
+            FileInfo::FINF(_) => ChunkKind::FINF,
+            FileInfo::FI64(_) => ChunkKind::FI64,
+            FileInfo::FEND(_) => ChunkKind::FEND,
+        }
+    }
+
+    pub(crate) fn byte_len(&self) -> u32 {
+        match self {
+            FileInfo::FINF(finf) => finf.byte_len,
+            FileInfo::FI64(fi64) => fi64.byte_len,
+            FileInfo::FEND(fend) => fend.byte_len,
+        }
+    }
+
+    /// Returns the padding bytes of a `FINF` or `FI64` chunk, in the order they
+    /// appear.
+    pub(crate) fn padding(&self) -> Vec<u8> {
+        match self {
+            FileInfo::FINF(finf) => [&finf.mode_padding[..], &finf.trailing_padding].concat(),
+            FileInfo::FI64(fi64) => [&fi64.mode_padding[..], &fi64.trailing_padding].concat(),
+            FileInfo::FEND(_) => Vec::new(),
+        }
+    }
+}
+
 impl TryFrom<FileInfo> for FI64 {
     type Error = Box<dyn std::error::Error>;
 
     fn try_from(file_info: FileInfo) -> Result<Self, Self::Error> {
         match file_info {
             FileInfo::FINF(finf) => Ok(FI64 {
+                // FI64 chunks have more trailing padding than FINF chunks.
+                trailing_padding: {
+                    let mut trailing_padding = [0; 7];
+                    trailing_padding[..3].copy_from_slice(&finf.trailing_padding);
+                    trailing_padding
+                },
                 byte_len: finf.byte_len,
                 filename: finf.filename,
+                name_offset: u64::from(finf.name_offset),
                 file_size: u64::from(finf.file_size),
                 data_offset: u64::from(finf.data_offset),
                 data_size: u64::from(finf.data_size),
                 mode: finf.mode,
+                mode_padding: finf.mode_padding,
                 data_hmac: finf.data_hmac,
                 data_md5: finf.data_md5,
             }),
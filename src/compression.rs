@@ -0,0 +1,42 @@
+use std::io::Read;
+
+use crate::spk::ReadError;
+
+pub(crate) const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+#[cfg(feature = "xz")]
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// Returns a reader that decompresses `data`, identifying the codec from the
+/// header at the start of the stream.
+///
+/// gzip and zlib streams are always supported, while zstd and xz streams
+/// require the `zstd` and `xz` features.
+pub(crate) fn decoder<'r>(data: &'r [u8]) -> Result<Box<dyn Read + 'r>, ReadError> {
+    if data.starts_with(GZIP_MAGIC) {
+        return Ok(Box::new(flate2::read::GzDecoder::new(data)));
+    }
+    #[cfg(feature = "zstd")]
+    if data.starts_with(ZSTD_MAGIC) {
+        return Ok(Box::new(zstd::stream::read::Decoder::new(data)?));
+    }
+    #[cfg(feature = "xz")]
+    if data.starts_with(XZ_MAGIC) {
+        return Ok(Box::new(liblzma::read::XzDecoder::new(data)));
+    }
+    if is_zlib_header(data) {
+        return Ok(Box::new(flate2::read::ZlibDecoder::new(data)));
+    }
+
+    Err(ReadError::UnsupportedCompression)
+}
+
+// A zlib stream starts with a CMF byte using the deflate method followed by a
+// FLG byte chosen such that the pair, read as a big-endian u16, is a multiple of 31.
+fn is_zlib_header(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0,
+        _ => false,
+    }
+}
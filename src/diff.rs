@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::spk;
+
+/// The changes between two archives, as returned by [`SPKFile::diff`](spk::SPKFile::diff).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    /// The changes within each package that has any, by package name.
+    pub packages: BTreeMap<String, PackageDiff>,
+}
+
+/// The changes to the files within a package. File names are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<ModifiedFile>,
+}
+
+/// A file present in both archives that differs between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedFile {
+    pub name: String,
+    pub size_changed: bool,
+    pub md5_changed: bool,
+    pub hmac_changed: bool,
+}
+
+impl PackageDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+// The files of the packages in `file`, by package name and then file name.
+fn files_by_package<'f>(
+    file: &'f spk::SPKFile,
+) -> BTreeMap<&'f str, BTreeMap<&'f str, &'f spk::FileInfo>> {
+    let mut packages: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+    for (package, file_info) in file.iter_files() {
+        packages
+            .entry(package.name.as_str())
+            .or_default()
+            .insert(file_info.name.as_str(), file_info);
+    }
+    packages
+}
+
+impl spk::SPKFile<'_> {
+    /// Returns the files in this archive that aren't present in `older`.
+    ///
+    /// Files are matched by the name of their package and their file name.
+    #[must_use]
+    pub fn files_added_since(&self, older: &spk::SPKFile) -> Vec<(&spk::Package, &spk::FileInfo)> {
+        let existing: HashSet<_> = older
+            .iter_files()
+            .map(|(package, file_info)| (package.name.as_str(), file_info.name.as_str()))
+            .collect();
+
+        self.iter_files()
+            .filter(|(package, file_info)| {
+                !existing.contains(&(package.name.as_str(), file_info.name.as_str()))
+            })
+            .collect()
+    }
+
+    /// Compares this archive with `other`, reporting the files added, removed,
+    /// and modified in `other`.
+    ///
+    /// Files are matched by the name of their package and their file name, and
+    /// are modified if their size, MD5, or HMAC differ. Only the archives'
+    /// metadata is compared, so no file data is read.
+    #[must_use]
+    pub fn diff(&self, other: &spk::SPKFile) -> Diff {
+        let old = files_by_package(self);
+        let new = files_by_package(other);
+
+        let mut diff = Diff::default();
+        let names: BTreeSet<_> = old.keys().chain(new.keys()).copied().collect();
+        for name in names {
+            let empty = BTreeMap::new();
+            let old_files = old.get(name).unwrap_or(&empty);
+            let new_files = new.get(name).unwrap_or(&empty);
+
+            let mut package_diff = PackageDiff::default();
+            for (&file_name, old_file) in old_files {
+                let Some(new_file) = new_files.get(file_name) else {
+                    package_diff.removed.push(file_name.to_string());
+                    continue;
+                };
+
+                let modified = ModifiedFile {
+                    name: file_name.to_string(),
+                    size_changed: old_file.size != new_file.size,
+                    md5_changed: old_file.md5 != new_file.md5,
+                    hmac_changed: old_file.hmac != new_file.hmac,
+                };
+                if modified.size_changed || modified.md5_changed || modified.hmac_changed {
+                    package_diff.modified.push(modified);
+                }
+            }
+            package_diff.added = new_files
+                .keys()
+                .filter(|file_name| !old_files.contains_key(*file_name))
+                .map(|file_name| (*file_name).to_string())
+                .collect();
+
+            if !package_diff.is_empty() {
+                diff.packages.insert(name.to_string(), package_diff);
+            }
+        }
+        diff
+    }
+}
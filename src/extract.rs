@@ -1,8 +1,32 @@
-use std::{io::Write as _, path::Path};
+use std::{
+    collections::HashMap,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context as _;
+use md5::Digest as _;
+use thiserror::Error;
 
-use crate::{spk, verify};
+use crate::{manifest, spk, verify};
+
+#[derive(Error, Debug)]
+pub enum ExtractError {
+    #[error("Failed to write file: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to read file from archive: {0}")]
+    Read(#[from] spk::ReadError),
+    #[error("Refusing to extract file whose path is absolute: {0}")]
+    AbsolutePath(String),
+    #[error("Refusing to extract file whose path leaves the destination: {0}")]
+    PathTraversal(String),
+    #[error("Refusing to extract file beneath symlink {}", .0.display())]
+    SymlinkInPath(PathBuf),
+    #[error("Failed to get parent directory for {}", .0.display())]
+    NoParentDirectory(PathBuf),
+    #[error("No package named {0}")]
+    PackageNotFound(String),
+}
 
 pub fn extract(file: &mut spk::SPKFile, to: &Path) -> anyhow::Result<()> {
     match std::fs::remove_dir_all(to) {
@@ -32,31 +56,478 @@ pub fn extract(file: &mut spk::SPKFile, to: &Path) -> anyhow::Result<()> {
         );
 
         for file_info in &package.files {
-            if file_info.name.starts_with('/') {
-                anyhow::bail!(
-                    "Refusing to extract file whose path is absolute: {}",
-                    file_info.name
-                );
+            let output_path = output_path(&package_path, file_info)?;
+            println!("   {}", file_info.name);
+            file.extract_entry(&output_path, file_info, || Ok(file.read(file_info)?))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The number of files written and skipped by
+/// [`SPKFile::extract_package_incremental`](spk::SPKFile::extract_package_incremental).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalExtraction {
+    /// Files that were missing or differed from the archive.
+    pub written: usize,
+    /// Files that were already present and identical to the archive.
+    pub skipped: usize,
+}
+
+/// How far [`SPKFile::extract_to_with_progress`](spk::SPKFile::extract_to_with_progress)
+/// has got, reported after each file is extracted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractProgress {
+    pub files_done: usize,
+    pub total_files: usize,
+    /// The number of bytes of file contents written so far.
+    pub bytes_written: u64,
+}
+
+// The directory within `dest` that the files of `package` are installed to.
+fn installed_path(dest: &Path, package: &spk::Package) -> PathBuf {
+    dest.join(package.type_.path_prefix().trim_start_matches('/'))
+}
+
+// The path that `file_info` is extracted to within `package_path`.
+//
+// Paths that would leave `package_path`, whether through `..` components or
+// through a symlink extracted earlier, are rejected so that an archive can only
+// write within the destination.
+fn output_path(package_path: &Path, file_info: &spk::FileInfo) -> Result<PathBuf, ExtractError> {
+    if file_info.name.starts_with('/') {
+        Err(ExtractError::AbsolutePath(file_info.name.clone()))?;
+    }
+
+    let name = Path::new(&file_info.name);
+    let mut output_path = package_path.to_path_buf();
+    for component in name.components() {
+        match component {
+            std::path::Component::Normal(component) => {
+                // Only the final component may be a symlink, which is replaced
+                // rather than followed when the file is written.
+                if output_path != package_path
+                    && std::fs::symlink_metadata(&output_path)
+                        .is_ok_and(|metadata| metadata.is_symlink())
+                {
+                    Err(ExtractError::SymlinkInPath(output_path.clone()))?;
+                }
+                output_path.push(component);
             }
+            std::path::Component::CurDir => {}
+            _ => Err(ExtractError::PathTraversal(file_info.name.clone()))?,
+        }
+    }
 
-            println!("   {}", file_info.name);
-            let output_path = package_path.join(&file_info.name);
-            let parent = output_path.parent().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Failed to get parent directory for {}",
-                    output_path.display()
-                )
+    Ok(output_path)
+}
+
+// Applies the permissions of `file_info` to `output_path`. Only Unix has
+// equivalent permission bits, so elsewhere this does nothing.
+fn set_permissions(output_path: &Path, file_info: &spk::FileInfo) -> std::io::Result<()> {
+    #[cfg(unix)]
+    std::fs::set_permissions(
+        output_path,
+        std::os::unix::fs::PermissionsExt::from_mode(file_info.unix_permissions()),
+    )?;
+    #[cfg(not(unix))]
+    let _ = (output_path, file_info);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_symlink(output_path: &Path, target: &Path) -> Result<(), ExtractError> {
+    let parent = output_path
+        .parent()
+        .ok_or_else(|| ExtractError::NoParentDirectory(output_path.to_path_buf()))?;
+
+    std::fs::create_dir_all(parent)?;
+
+    // Unlike writing a file, creating a symlink fails if the path already exists.
+    match std::fs::remove_file(output_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => Err(err)?,
+    }
+    std::os::unix::fs::symlink(target, output_path)?;
+
+    Ok(())
+}
+
+fn write_file(
+    output_path: &Path,
+    file_info: &spk::FileInfo,
+    contents: &[u8],
+) -> Result<(), ExtractError> {
+    let parent = output_path
+        .parent()
+        .ok_or_else(|| ExtractError::NoParentDirectory(output_path.to_path_buf()))?;
+
+    std::fs::create_dir_all(parent)?;
+
+    // Writing to an existing symlink would overwrite its target instead.
+    if std::fs::symlink_metadata(output_path).is_ok_and(|metadata| metadata.is_symlink()) {
+        std::fs::remove_file(output_path)?;
+    }
+    std::fs::write(output_path, contents)?;
+    set_permissions(output_path, file_info)?;
+
+    Ok(())
+}
+
+impl spk::SPKFile<'_> {
+    /// Extracts the files of every package to `dest`, recreating the layout
+    /// they are installed with.
+    ///
+    /// Each package's files are placed under its
+    /// [`path_prefix`](crate::chunks::PackageType::path_prefix) within `dest`,
+    /// so game packages are extracted to `dest/games`. Intermediate directories
+    /// are created as needed.
+    pub fn extract_to(&self, dest: &Path) -> Result<(), ExtractError> {
+        self.extract_to_with_progress(dest, &mut |_| {})
+    }
+
+    /// Extracts the files of every package to `dest` as
+    /// [`SPKFile::extract_to`](spk::SPKFile::extract_to) does, calling
+    /// `progress` after each file is extracted.
+    pub fn extract_to_with_progress(
+        &self,
+        dest: &Path,
+        progress: &mut dyn FnMut(ExtractProgress),
+    ) -> Result<(), ExtractError> {
+        let mut state = ExtractProgress {
+            total_files: self
+                .packages
+                .iter()
+                .map(|package| package.files.len())
+                .sum(),
+            ..ExtractProgress::default()
+        };
+        for package in &self.packages {
+            self.extract_package_files(package, &installed_path(dest, package), &mut |written| {
+                state.files_done += 1;
+                state.bytes_written += written;
+                progress(state);
             })?;
+        }
 
-            std::fs::create_dir_all(parent)?;
+        Ok(())
+    }
 
-            std::fs::write(&output_path, file.read(file_info)?)?;
-            std::fs::set_permissions(
-                &output_path,
-                std::os::unix::fs::PermissionsExt::from_mode(u32::from(file_info.mode)),
-            )?;
+    /// Reads the files of every package into memory rather than extracting
+    /// them to disk, mapping each file's installed path to its stored
+    /// contents.
+    ///
+    /// Directories are skipped, and symlinks map to the bytes of their target.
+    /// If several files share a path, the last one read is kept.
+    pub fn extract_to_map(&self) -> Result<HashMap<String, Vec<u8>>, spk::ReadError> {
+        let mut files = HashMap::new();
+        for (package, file_info) in self.iter_files() {
+            if file_info.file_type() == spk::EntryKind::Directory {
+                continue;
+            }
+            files.insert(file_info.full_path(package), self.read(file_info)?);
         }
+
+        Ok(files)
     }
 
-    Ok(())
+    /// Extracts the files of the package named `name` to `dest`, placing them
+    /// under its path prefix as [`SPKFile::extract_to`](spk::SPKFile::extract_to)
+    /// does.
+    pub fn extract_package(&self, name: &str, dest: &Path) -> Result<(), ExtractError> {
+        let package = self
+            .packages
+            .iter()
+            .find(|package| package.name == name)
+            .ok_or_else(|| ExtractError::PackageNotFound(name.to_string()))?;
+        self.extract_package_files(package, &installed_path(dest, package), &mut |_| {})
+    }
+
+    /// Extracts the files of `package` to `dest`, setting each file's
+    /// modification time to the timestamp stored for it in the archive.
+    ///
+    /// No field in the format has yet been identified as a timestamp, so
+    /// files are currently left with the time at which they were written, as
+    /// with any other extraction.
+    pub fn extract_package_with_times(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+    ) -> Result<(), ExtractError> {
+        self.extract_package_files(package, dest, &mut |_| {})
+    }
+
+    /// Extracts the files of `package` to `dest`, then writes a manifest of the
+    /// package's contents to [`manifest::MANIFEST_FILE_NAME`] within `dest`.
+    ///
+    /// The manifest describes the package as
+    /// [`SPKFile::manifest_json`](spk::SPKFile::manifest_json) does, recording
+    /// each file's name, size, mode, and expected MD5 and HMAC so the
+    /// extracted files can later be checked without the archive.
+    pub fn extract_package_with_manifest(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+    ) -> Result<(), ExtractError> {
+        self.extract_package_files(package, dest, &mut |_| {})?;
+
+        let manifest_path = dest.join(manifest::MANIFEST_FILE_NAME);
+        std::fs::write(&manifest_path, manifest::package_manifest(package))?;
+
+        Ok(())
+    }
+
+    /// Extracts the files of `package` to `dest`, passing the contents of each
+    /// file through `transform` before it is written.
+    ///
+    /// `transform` is given the file's name and its complete contents, so each
+    /// file is held in memory in its entirety, along with whatever `transform`
+    /// returns, while it is being processed. Only regular files are
+    /// transformed, while directories and symlinks are extracted as they are.
+    pub fn extract_package_transformed(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+        transform: impl Fn(&str, &[u8]) -> Vec<u8>,
+    ) -> Result<(), ExtractError> {
+        for file_info in &package.files {
+            let output_path = output_path(dest, file_info)?;
+            self.extract_entry(&output_path, file_info, || {
+                Ok(transform(&file_info.name, &self.read(file_info)?))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Extracts the files of `package` to `dest`, skipping files that already
+    /// exist in `dest` with the expected size and MD5.
+    ///
+    /// Existing files are hashed to compare them, which is much cheaper than
+    /// writing them again when only a few files have changed. Directories are
+    /// skipped if they exist and symlinks if they have the expected target,
+    /// and existing symlinks are replaced rather than written through.
+    pub fn extract_package_incremental(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+    ) -> Result<IncrementalExtraction, ExtractError> {
+        let mut result = IncrementalExtraction::default();
+        for file_info in &package.files {
+            let output_path = output_path(dest, file_info)?;
+            if self.is_unchanged(&output_path, file_info)? {
+                result.skipped += 1;
+                continue;
+            }
+
+            self.extract_entry(&output_path, file_info, || Ok(self.read(file_info)?))?;
+            result.written += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Extracts the files of `package` to `dest`, reading files on a background
+    /// thread while earlier files are being written.
+    ///
+    /// Up to `depth` files are read ahead of the file being written, which
+    /// overlaps reading and writing on high-latency storage at the cost of
+    /// holding those files in memory.
+    pub fn extract_package_prefetched(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+        depth: usize,
+    ) -> Result<(), ExtractError> {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(depth);
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for file_info in &package.files {
+                    // Sending fails once the writer has stopped, so there's no point reading on.
+                    if sender.send((file_info, self.read(file_info))).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            for (file_info, contents) in receiver {
+                let output_path = output_path(dest, file_info)?;
+                self.extract_entry(&output_path, file_info, || Ok(contents?))?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Extracts the stored contents of `file_info` to `dest_path`, reading them
+    /// directly into a memory mapping of the destination file.
+    ///
+    /// This avoids an intermediate buffer, which can be faster than buffered
+    /// writes for very large files. The mapping is flushed and the file synced
+    /// before returning.
+    #[cfg(feature = "mmap")]
+    pub fn extract_file_mmap(
+        &self,
+        file_info: &spk::FileInfo,
+        dest_path: &Path,
+    ) -> Result<(), ExtractError> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dest_path)?;
+
+        // Empty files can't be mapped, and have nothing to read anyway.
+        if file_info.data_size > 0 {
+            file.set_len(file_info.data_size)?;
+            // SAFETY: The file was truncated and sized above and is only
+            // modified through this mapping until it is dropped.
+            let mut mapping = unsafe { memmap2::MmapMut::map_mut(&file)? };
+            self.read_exact_at(file_info.offset, &mut mapping)?;
+            mapping.flush()?;
+        }
+
+        set_permissions(dest_path, file_info)?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    // Extracts the files of `package` to `dest`, calling `on_file` with the
+    // number of bytes written after each file.
+    fn extract_package_files(
+        &self,
+        package: &spk::Package,
+        dest: &Path,
+        on_file: &mut dyn FnMut(u64),
+    ) -> Result<(), ExtractError> {
+        for file_info in &package.files {
+            let output_path = output_path(dest, file_info)?;
+            on_file(self.extract_entry(&output_path, file_info, || Ok(self.read(file_info)?))?);
+        }
+
+        Ok(())
+    }
+
+    // Whether `output_path` already holds exactly what extracting `file_info`
+    // would create there. Existing symlinks are inspected rather than followed.
+    fn is_unchanged(
+        &self,
+        output_path: &Path,
+        file_info: &spk::FileInfo,
+    ) -> Result<bool, ExtractError> {
+        let metadata = match std::fs::symlink_metadata(output_path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => Err(err)?,
+        };
+
+        match file_info.file_type() {
+            spk::EntryKind::Directory => Ok(metadata.is_dir()),
+            #[cfg(unix)]
+            spk::EntryKind::Symlink => Ok(metadata.is_symlink()
+                && std::fs::read_link(output_path)? == self.read_symlink(file_info)?),
+            _ => {
+                if !metadata.is_file() || metadata.len() != file_info.data_size {
+                    return Ok(false);
+                }
+
+                let mut md5 = md5::Md5::new();
+                std::io::copy(&mut std::fs::File::open(output_path)?, &mut md5)?;
+                Ok(md5.finalize() == file_info.md5.into())
+            }
+        }
+    }
+
+    // Extracts `file_info` to `output_path` according to its type, returning
+    // the number of bytes of contents written. Directories are created and
+    // symlinks recreated, while regular files are written with the contents
+    // returned by `contents`, which is only called for regular files.
+    fn extract_entry(
+        &self,
+        output_path: &Path,
+        file_info: &spk::FileInfo,
+        contents: impl FnOnce() -> Result<Vec<u8>, ExtractError>,
+    ) -> Result<u64, ExtractError> {
+        match file_info.file_type() {
+            spk::EntryKind::Directory => {
+                std::fs::create_dir_all(output_path)?;
+                Ok(0)
+            }
+            #[cfg(unix)]
+            spk::EntryKind::Symlink => {
+                write_symlink(output_path, &self.read_symlink(file_info)?)?;
+                Ok(0)
+            }
+            _ => {
+                let contents = contents()?;
+                write_file(output_path, file_info, &contents)?;
+                Ok(contents.len() as u64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        SPKWriter, chunks, spk,
+        writer::{NewFile, NewPackage},
+    };
+
+    use super::ExtractError;
+
+    fn archive(files: &[(&str, u16, &[u8])]) -> spk::SPKFile<'static> {
+        let mut writer = SPKWriter::new();
+        writer.add_package(NewPackage {
+            name: "test".to_string(),
+            package_id: [0; 3],
+            version: spk::Version::default(),
+            type_: chunks::PackageType::Spike2,
+            uncompressed_size: None,
+            sidx_trailer: [0; 12],
+            files: files
+                .iter()
+                .map(|(name, mode, data)| NewFile {
+                    name: name.as_bytes().to_vec(),
+                    size: data.len() as u64,
+                    mode: *mode,
+                    data: data.to_vec(),
+                })
+                .collect(),
+        });
+        let mut contents = Vec::new();
+        writer.write(&mut contents).unwrap();
+        spk::SPKFile::parse(Cursor::new(contents)).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extraction_does_not_follow_extracted_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+
+        let spk = archive(&[
+            ("link", 0o120_777, outside.as_os_str().as_encoded_bytes()),
+            ("link/escaped", 0o100_644, b"contents"),
+        ]);
+        let result = spk.extract_to(&dir.path().join("dest"));
+        assert!(matches!(result, Err(ExtractError::SymlinkInPath(_))));
+        assert!(!outside.join("escaped").exists());
+    }
+
+    #[test]
+    fn extraction_rejects_parent_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let spk = archive(&[("../escaped", 0o100_644, b"contents")]);
+        let result = spk.extract_to(&dir.path().join("dest"));
+        assert!(matches!(result, Err(ExtractError::PathTraversal(_))));
+    }
 }
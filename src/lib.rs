@@ -1,7 +1,18 @@
+pub mod chunks;
+pub mod diff;
 pub mod extract;
+pub mod manifest;
 pub mod spk;
+pub mod squashed;
+pub mod streaming;
 pub mod verify;
+pub mod writer;
 pub use spk::SPKFile;
+pub use writer::SPKWriter;
 
-mod chunks;
-mod squashed;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod compression;
+#[cfg(feature = "serde")]
+mod serialize;
+mod tree;
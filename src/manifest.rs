@@ -0,0 +1,120 @@
+use std::fmt::Write as _;
+
+use crate::spk;
+
+/// The name of the manifest written alongside extracted package contents.
+pub const MANIFEST_FILE_NAME: &str = ".spk-manifest.json";
+
+/// Renders a JSON manifest describing `package` and the expected size, mode,
+/// and digests of each of its files.
+///
+/// The package is described as it is within
+/// [`SPKFile::manifest_json`](spk::SPKFile::manifest_json), so both manifests
+/// share a schema.
+pub(crate) fn package_manifest(package: &spk::Package) -> String {
+    let mut out = String::new();
+    write_package(&mut out, package, 0);
+    out.push('\n');
+    out
+}
+
+impl spk::SPKFile<'_> {
+    /// Renders a JSON manifest listing every package in the archive with its
+    /// name, version, type, and game ID, and each of its files with its
+    /// installed path and the fields it is serialized with by serde.
+    ///
+    /// Packages and files are sorted by name so that manifests of different
+    /// builds can be diffed.
+    #[must_use]
+    pub fn manifest_json(&self) -> String {
+        let mut packages: Vec<_> = self.packages.iter().collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut out = String::new();
+        out.push_str("{\n  \"packages\": [");
+        for (i, package) in packages.iter().enumerate() {
+            out.push_str(if i == 0 { "\n" } else { ",\n" });
+            write_package(&mut out, package, 4);
+        }
+        out.push_str(if packages.is_empty() {
+            "]\n"
+        } else {
+            "\n  ]\n"
+        });
+        out.push_str("}\n");
+        out
+    }
+}
+
+// Writes `package` as a JSON object, without a trailing newline, with each line
+// indented by `indent` spaces. Files are listed sorted by name, each with the
+// fields it is serialized with by serde along with its installed path, so the
+// manifests and the serde output share a schema.
+fn write_package(out: &mut String, package: &spk::Package, indent: usize) {
+    let pad = " ".repeat(indent);
+    let mut files: Vec<_> = package.files.iter().collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let _ = writeln!(out, "{pad}{{");
+    let _ = writeln!(out, "{pad}  \"name\": {},", string(&package.name));
+    let _ = writeln!(out, "{pad}  \"version\": \"{}\",", package.version);
+    let _ = writeln!(out, "{pad}  \"type\": \"{}\",", package.type_);
+    let _ = writeln!(
+        out,
+        "{pad}  \"game_id\": {},",
+        package
+            .game_id
+            .as_deref()
+            .map_or("null".to_string(), string)
+    );
+    let _ = write!(out, "{pad}  \"files\": [");
+    for (i, file_info) in files.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        let _ = write!(
+            out,
+            "{pad}    {{ \"name\": {}, \"path\": {}, \"raw_name\": \"{}\", \"size\": {}, \"mode\": {}, \"mode_string\": \"{}\", \"md5\": \"{}\", \"hmac\": \"{}\", \"is_64bit\": {} }}",
+            string(&file_info.name),
+            string(&file_info.full_path(package)),
+            hex(&file_info.raw_name),
+            file_info.size,
+            file_info.mode,
+            file_info.mode_string(),
+            file_info.md5_hex(),
+            file_info.hmac_hex(),
+            file_info.is_64bit
+        );
+    }
+    if files.is_empty() {
+        out.push_str("]\n");
+    } else {
+        let _ = writeln!(out, "\n{pad}  ]");
+    }
+    let _ = write!(out, "{pad}}}");
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+fn string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,37 @@
+use serde::ser::{Serialize, SerializeStruct as _, Serializer};
+
+use crate::{chunks, manifest, spk};
+
+// Versions and package types are serialized as they are displayed, such as
+// "1.2.3" and "spike2".
+
+impl Serialize for spk::Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for chunks::PackageType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Files are serialized with their raw name and digests as lowercase hex and
+// their mode both as a number and as `ls -l` would show it. Offsets within the
+// archive are omitted. The JSON manifests describe files with the same fields,
+// adding the installed path, which depends on the file's package.
+impl Serialize for spk::FileInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut file = serializer.serialize_struct("FileInfo", 8)?;
+        file.serialize_field("name", &self.name)?;
+        file.serialize_field("raw_name", &manifest::hex(&self.raw_name))?;
+        file.serialize_field("size", &self.size)?;
+        file.serialize_field("mode", &self.mode)?;
+        file.serialize_field("mode_string", &self.mode_string())?;
+        file.serialize_field("md5", &self.md5_hex())?;
+        file.serialize_field("hmac", &self.hmac_hex())?;
+        file.serialize_field("is_64bit", &self.is_64bit)?;
+        file.end()
+    }
+}
@@ -1,15 +1,18 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
     ffi::{CStr, FromBytesUntilNulError, OsStr},
-    io::Cursor,
-    path::Path,
+    io::{Cursor, Read as _, Seek as _},
+    path::{Path, PathBuf},
     result::Result,
     sync::{Arc, Mutex},
 };
 
 use binrw::{BinRead, PosValue};
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use sha2::Digest as _;
 use thiserror::Error;
 
-use crate::{chunks, squashed};
+use crate::{chunks, compression, manifest, squashed};
 
 pub(crate) const HMAC_KEY: &[u8] = &[
     0x8e, 0x1f, 0x55, 0x43, 0xc2, 0xf5, 0x4a, 0x11, 0x67, 0x3a, 0x28, 0x2a, 0x2f, 0x87, 0xc0, 0x06,
@@ -33,6 +36,60 @@ pub enum OpenError {
     UnknownFileType,
     #[error("Directory does not appear to contain a split SPK file")]
     DirectoryDoesNotContainSplitSPK,
+    #[error("Directory contains {} split SPK files, expected one", .0.len())]
+    DirectoryContainsMultipleSplitSPKs(Vec<PathBuf>),
+    #[error(
+        "No package has game ID {}, found: {}",
+        .expected.escape_ascii(),
+        game_ids(.found)
+    )]
+    UnexpectedGameId {
+        expected: [u8; 3],
+        found: Vec<[u8; 3]>,
+    },
+    #[error(
+        "Failed to parse {chunk} chunk at offset {offset}{}: {source}",
+        package_context(*.package)
+    )]
+    Chunk {
+        /// The index of the package being parsed, if any.
+        package: Option<usize>,
+        /// The magic of the chunk (or chunks) expected at `offset`.
+        chunk: &'static str,
+        offset: u64,
+        source: binrw::Error,
+    },
+    #[error("Unexpected chunk at offset {offset}")]
+    UnexpectedChunk { offset: u64 },
+    #[error("File {file} has non-zero padding: {padding:02x?}")]
+    NonZeroPadding { file: String, padding: Vec<u8> },
+    #[error("Data offset {offset} of file {file} overflows")]
+    InvalidOffset { file: String, offset: u64 },
+    #[error("Data of file {0} overlaps data that was already read from the stream")]
+    OverlappingData(String),
+}
+
+// The game ID stored in `package_id`, if it holds an ASCII ID rather than NULs.
+fn game_id(package_id: [u8; 3]) -> Option<String> {
+    let id = package_id
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(&package_id[..], |end| &package_id[..end]);
+    std::str::from_utf8(id)
+        .ok()
+        .filter(|id| !id.is_empty() && id.is_ascii())
+        .map(str::to_string)
+}
+
+fn package_context(package: Option<usize>) -> String {
+    package.map_or_else(String::new, |index| format!(" in package {index}"))
+}
+
+fn game_ids(ids: &[[u8; 3]]) -> String {
+    ids.iter()
+        .map(|id| id.escape_ascii().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[derive(Error, Debug)]
@@ -41,14 +98,144 @@ pub enum ReadError {
     IOError(#[from] std::io::Error),
     #[error("Failed to parse file: {0}")]
     Parse(#[from] binrw::Error),
+    #[error("File data is compressed in an unsupported format")]
+    UnsupportedCompression,
+    #[error("File data failed verification")]
+    VerificationFailed,
+    #[error("File data decompressed to {actual} bytes, expected {expected}")]
+    DecompressedSizeMismatch { expected: u64, actual: u64 },
+    #[error(
+        "File data has MD5 {}, expected {}",
+        manifest::hex(.actual),
+        manifest::hex(.expected)
+    )]
+    ChecksumMismatch {
+        expected: [u8; 16],
+        actual: [u8; 16],
+    },
+    #[error("File {0} is not a symlink")]
+    NotSymlink(String),
+    #[error("Symlink target contained invalid UTF-8: {0}")]
+    SymlinkTarget(#[from] std::string::FromUtf8Error),
+    #[error("Range starts at {start}, past the end of the {data_size}-byte file")]
+    InvalidRange { start: u64, data_size: u64 },
+    #[error("Archive ended after {copied} of {expected} bytes of file data")]
+    Truncated { expected: u64, copied: u64 },
+    #[error(
+        "{data_size} bytes at offset {offset} extend past the end of the {archive_len}-byte archive"
+    )]
+    OutOfBounds {
+        offset: u64,
+        data_size: u64,
+        archive_len: u64,
+    },
 }
 
+#[derive(Error, Debug)]
+pub enum LayoutError {
+    #[error("Archive layout is unknown because its SPKS chunk couldn't be parsed")]
+    Unknown,
+    #[error(
+        "SPKS chunk ends at offset {declared_end}, past the end of the {archive_len}-byte archive"
+    )]
+    Truncated { declared_end: u64, archive_len: u64 },
+    #[error("SPKS chunk declares {declared} packages, but {parsed} were parsed")]
+    ChunkCountMismatch { declared: u32, parsed: usize },
+    #[error("SPKS chunk ends at offset {declared_end}, but its packages end at {parsed_end}")]
+    LengthMismatch { declared_end: u64, parsed_end: u64 },
+}
+
+// The amount of data read at a time when scanning a file's contents.
+const READ_BLOCK_SIZE: u64 = 64 * 1024;
+
 trait SeekableReader: std::io::Read + std::io::Seek + Send {}
 impl<T> SeekableReader for T where T: std::io::Read + std::io::Seek + Send {}
 
+// A reader over `inner` that presents the data from `base` onwards as if it
+// started at offset 0.
+struct Skipped<R> {
+    inner: R,
+    base: u64,
+}
+
+impl<R: std::io::Read> std::io::Read for Skipped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: std::io::Seek> std::io::Seek for Skipped<R> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            std::io::SeekFrom::Start(offset) => {
+                std::io::SeekFrom::Start(self.base.checked_add(offset).ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "Seek offset overflowed")
+                })?)
+            }
+            pos => pos,
+        };
+        let position = self.inner.seek(pos)?;
+        position.checked_sub(self.base).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Seeked before the start of the archive",
+            )
+        })
+    }
+}
+
+/// A reader that knows the total length of the data it reads from.
+///
+/// Archives parsed from a `LenReader` check reads against this length, so a
+/// truncated archive fails with [`ReadError::OutOfBounds`].
+pub trait LenReader: std::io::Read + std::io::Seek + Send {
+    fn total_len(&self) -> u64;
+}
+
+impl LenReader for std::fs::File {
+    fn total_len(&self) -> u64 {
+        // If the length can't be determined, don't bound reads at all.
+        self.metadata().map_or(u64::MAX, |metadata| metadata.len())
+    }
+}
+
+impl LenReader for squashed::SplitReader {
+    fn total_len(&self) -> u64 {
+        self.len()
+    }
+}
+
+impl<T> LenReader for Cursor<T>
+where
+    T: AsRef<[u8]> + Send,
+{
+    fn total_len(&self) -> u64 {
+        self.get_ref().as_ref().len() as u64
+    }
+}
+
 pub struct SPKFile<'a> {
     pub packages: Vec<Package>,
     reader: Arc<Mutex<dyn SeekableReader + 'a>>,
+    // When set, reads open their own handle to this file instead of sharing `reader`.
+    pub(crate) path: Option<PathBuf>,
+    // The length of the archive, when known.
+    archive_len: Option<u64>,
+    // Maps package and file names to the index of the file within its package.
+    index: Option<HashMap<(String, String), usize>>,
+    // What the SPKS chunk declares, unless it couldn't be parsed.
+    layout: Option<Layout>,
+}
+
+// The package count and extent declared by an archive's SPKS chunk, alongside
+// where its packages were actually found to end.
+#[derive(Debug, Copy, Clone)]
+struct Layout {
+    chunk_count: u32,
+    declared_end: u64,
+    parsed_end: u64,
+    // Packages that parsed but were deliberately left out of `packages`.
+    filtered: usize,
 }
 
 impl std::fmt::Debug for SPKFile<'_> {
@@ -59,121 +246,1777 @@ impl std::fmt::Debug for SPKFile<'_> {
     }
 }
 
+/// A package's version number, formatted as `major.minor.patch`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid version number: {0}")]
+pub struct InvalidVersion(pub String);
+
+impl std::str::FromStr for Version {
+    type Err = InvalidVersion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidVersion(s.to_string());
+        let mut components = s.split('.').map(str::parse::<u8>);
+        let mut component = || {
+            components
+                .next()
+                .ok_or_else(invalid)?
+                .map_err(|_| invalid())
+        };
+        let version = Version {
+            major: component()?,
+            minor: component()?,
+            patch: component()?,
+        };
+        if components.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(version)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Package {
     pub name: String,
-    pub version: (u8, u8, u8),
+    /// The three-character game ID, such as `SKK`. All NULs in older updates.
+    pub package_id: [u8; 3],
+    /// The game ID as a string, or `None` in older updates without one.
+    pub game_id: Option<String>,
+    pub version: Version,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub type_: chunks::PackageType,
     pub files: Vec<FileInfo>,
+    /// The offset of the package's `SPK0` chunk within the archive.
+    pub offset: u64,
+    /// The offset of the package's `SIDX` chunk within the archive.
+    pub sidx_offset: u64,
+    /// The offset of the package's `STRS` chunk within the archive.
+    pub strs_offset: u64,
+    /// The length of the `STRS` chunk's string data, excluding its header.
+    pub strs_byte_len: u32,
+    /// The value of the package's `SZ64` chunk, if it has one. Its meaning
+    /// isn't known for certain, but it is most likely the package's
+    /// uncompressed size.
+    pub uncompressed_size: Option<u64>,
+    /// The last 12 bytes of the package's `SIDX` chunk, whose purpose is
+    /// unknown.
+    pub raw_sidx_trailer: [u8; 12],
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileInfo {
+    /// The file's name. Invalid UTF-8 in the stored name is replaced with U+FFFD.
     pub name: String,
+    /// The file's name exactly as stored in the archive.
+    pub raw_name: Vec<u8>,
+    // The offset of the name within the package's STRS string data.
+    pub(crate) name_offset: u64,
     pub size: u64,
     pub(crate) offset: u64,
+    // The data offset as stored, relative to the start of the package's SDAT data.
+    pub(crate) stored_offset: u64,
     pub(crate) data_size: u64,
     pub hmac: [u8; 20],
     pub md5: [u8; 16],
     pub mode: u16,
+    /// Whether the file was described by an `FI64` chunk rather than a `FINF`
+    /// chunk.
+    pub is_64bit: bool,
+}
+
+/// The type of an entry, as given by the file type bits of its mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    Regular,
+    Directory,
+    Symlink,
+    /// Any other file type, holding the file type bits of the mode.
+    Other(u16),
+}
+
+/// Aggregate figures describing a package's files, as returned by
+/// [`Package::stats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PackageStats {
+    pub file_count: usize,
+    pub dir_count: usize,
+    pub symlink_count: usize,
+    /// The total installed size of the package's files.
+    pub total_size: u64,
+    /// The total size of the package's file data as stored in the archive.
+    pub total_data_size: u64,
+}
+
+impl FileInfo {
+    /// Returns the type of the entry according to its mode.
+    #[must_use]
+    pub fn file_type(&self) -> EntryKind {
+        match self.mode & 0o170_000 {
+            0o100_000 => EntryKind::Regular,
+            0o040_000 => EntryKind::Directory,
+            0o120_000 => EntryKind::Symlink,
+            file_type => EntryKind::Other(file_type),
+        }
+    }
+
+    /// Returns the path the file is installed to on the machine: its name
+    /// prefixed with the path prefix of `package`, the package it belongs to.
+    #[must_use]
+    pub fn full_path(&self, package: &Package) -> String {
+        format!("{}{}", package.type_.path_prefix(), self.name)
+    }
+
+    /// Returns the permission bits of the file's mode, including the setuid,
+    /// setgid, and sticky bits but not the file type.
+    #[must_use]
+    pub fn unix_permissions(&self) -> u32 {
+        u32::from(self.mode) & 0o7777
+    }
+
+    /// Returns the file's MD5 as lowercase hex.
+    #[must_use]
+    pub fn md5_hex(&self) -> String {
+        manifest::hex(&self.md5)
+    }
+
+    /// Returns the file's HMAC as lowercase hex.
+    #[must_use]
+    pub fn hmac_hex(&self) -> String {
+        manifest::hex(&self.hmac)
+    }
+
+    /// Formats the file's mode the way `ls -l` does, such as `-rwxr-xr-x` for
+    /// an executable file or `lrwxrwxrwx` for a symlink.
+    ///
+    /// The setuid, setgid, and sticky bits are shown in place of the execute
+    /// permission they accompany, as `s` or `t` where that permission is set
+    /// and as `S` or `T` where it isn't.
+    #[must_use]
+    pub fn mode_string(&self) -> String {
+        let file_type = match self.mode & 0o170_000 {
+            0o100_000 => '-',
+            0o040_000 => 'd',
+            0o120_000 => 'l',
+            0o020_000 => 'c',
+            0o060_000 => 'b',
+            0o010_000 => 'p',
+            0o140_000 => 's',
+            _ => '?',
+        };
+
+        let mut out = String::with_capacity(10);
+        out.push(file_type);
+        // Each of the user, group, and other permissions, along with the setuid,
+        // setgid, or sticky bit shown in place of its execute permission.
+        for (shift, special, set, unset) in [
+            (6, 0o4000, 's', 'S'),
+            (3, 0o2000, 's', 'S'),
+            (0, 0o1000, 't', 'T'),
+        ] {
+            let bits = self.mode >> shift;
+            out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            out.push(match (bits & 0o1 != 0, self.mode & special != 0) {
+                (true, true) => set,
+                (false, true) => unset,
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        out
+    }
+}
+
+impl Package {
+    /// Computes a SHA-256 hash of the name, size, and MD5 of each of the
+    /// package's files.
+    ///
+    /// Files are hashed in order of name and their offsets aren't included, so
+    /// the hash only changes when the package's contents do, regardless of how
+    /// they are laid out in the archive.
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_by(|a, b| a.raw_name.cmp(&b.raw_name));
+
+        let mut sha256 = sha2::Sha256::new();
+        for file_info in files {
+            sha256.update((file_info.raw_name.len() as u64).to_le_bytes());
+            sha256.update(&file_info.raw_name);
+            sha256.update(file_info.size.to_le_bytes());
+            sha256.update(file_info.md5);
+        }
+        sha256.finalize().into()
+    }
+
+    /// Returns every directory that contains one of the package's files.
+    ///
+    /// Like file names, directories are relative to the package's root. A
+    /// directory always sorts before its subdirectories, so they can be created
+    /// in the order returned.
+    #[must_use]
+    pub fn implied_directories(&self) -> BTreeSet<String> {
+        self.files
+            .iter()
+            .flat_map(|file_info| {
+                file_info
+                    .name
+                    .match_indices('/')
+                    .map(|(i, _)| file_info.name[..i].to_string())
+            })
+            .filter(|directory| !directory.is_empty())
+            .collect()
+    }
+
+    /// Returns the total installed size of the package's files.
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file_info| file_info.size).sum()
+    }
+
+    /// Returns the total size of the package's file data as stored in the
+    /// archive.
+    #[must_use]
+    pub fn total_data_size(&self) -> u64 {
+        self.files.iter().map(|file_info| file_info.data_size).sum()
+    }
+
+    /// Counts the package's entries by type and totals their sizes.
+    ///
+    /// `file_count` counts every entry that isn't a directory or a symlink,
+    /// while the sizes cover every entry.
+    #[must_use]
+    pub fn stats(&self) -> PackageStats {
+        self.files
+            .iter()
+            .fold(PackageStats::default(), |mut stats, file_info| {
+                match file_info.file_type() {
+                    EntryKind::Directory => stats.dir_count += 1,
+                    EntryKind::Symlink => stats.symlink_count += 1,
+                    EntryKind::Regular | EntryKind::Other(_) => stats.file_count += 1,
+                }
+                stats.total_size += file_info.size;
+                stats.total_data_size += file_info.data_size;
+                stats
+            })
+    }
+
+    /// Sorts the package's files by name, keeping files with the same name in
+    /// their original order.
+    ///
+    /// Each file's offset is absolute, so its contents can still be read after
+    /// sorting.
+    pub fn sort_files(&mut self) {
+        self.files.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Returns the index pairs of the package's files whose data overlaps,
+    /// with the lower index first.
+    ///
+    /// Files without data never overlap. Overlapping data suggests a misparse
+    /// or a badly laid out archive, although identical files may share data.
+    #[must_use]
+    pub fn check_overlaps(&self) -> Vec<(usize, usize)> {
+        let mut by_offset: Vec<_> = (0..self.files.len())
+            .filter(|&i| self.files[i].data_size > 0)
+            .collect();
+        by_offset.sort_by_key(|&i| self.files[i].offset);
+
+        // Sweep through the files in order of offset, keeping those whose data
+        // extends past the start of the current file.
+        let mut overlaps = Vec::new();
+        let mut active: Vec<usize> = Vec::new();
+        for i in by_offset {
+            let start = self.files[i].offset;
+            active.retain(|&j| self.files[j].offset + self.files[j].data_size > start);
+            overlaps.extend(active.iter().map(|&j| (i.min(j), i.max(j))));
+            active.push(i);
+        }
+        overlaps.sort_unstable();
+        overlaps
+    }
+
+    /// Returns the number of the package's files that were described by `FI64`
+    /// chunks rather than `FINF` chunks.
+    #[must_use]
+    pub fn count_64bit_entries(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|file_info| file_info.is_64bit)
+            .count()
+    }
+
+    /// Whether any of the package's files are stored compressed, judging by
+    /// their stored size differing from their installed size.
+    #[must_use]
+    pub fn is_compressed(&self) -> bool {
+        self.files
+            .iter()
+            .any(|file_info| file_info.data_size != file_info.size)
+    }
+
+    /// Computes the size of the `SPK0` chunk that writing out this package
+    /// would produce, including its header.
+    ///
+    /// This assumes the package is laid out as [`SPKWriter`](crate::SPKWriter)
+    /// writes it: each file's name is stored once in the `STRS` chunk, even
+    /// if the archive shares names between files, the file data is laid out
+    /// contiguously in the order the files are listed, each file is described
+    /// by a `FINF` chunk unless its sizes or offsets need an `FI64` chunk, and
+    /// each chunk uses the shorter 32-bit length form whenever its length
+    /// fits.
+    #[must_use]
+    pub fn serialized_size(&self) -> u64 {
+        let mut strs_len = 0;
+        let mut file_infos_len = 0;
+        let mut data_len = 0;
+        for file_info in &self.files {
+            let name_offset = strs_len;
+            strs_len += file_info.raw_name.len() as u64 + 1;
+            let fits_finf = [name_offset, file_info.size, data_len, file_info.data_size]
+                .into_iter()
+                .all(|value| u32::try_from(value).is_ok());
+            file_infos_len += if fits_finf {
+                chunks::FINF::HEADER_LEN
+            } else {
+                chunks::FI64::HEADER_LEN
+            };
+            data_len += file_info.data_size;
+        }
+
+        let spk0_byte_len = chunks::SIDX::LEN
+            + if self.uncompressed_size.is_some() {
+                chunks::SZ64::LEN
+            } else {
+                0
+            }
+            + 8
+            + strs_len
+            + file_infos_len
+            + chunks::FEND::LEN
+            + chunks::ByteLen::smallest_header_size(data_len)
+            + data_len;
+        chunks::ByteLen::smallest_header_size(spk0_byte_len) + spk0_byte_len
+    }
+
+    /// Checks whether any file's name is stored within the string data of
+    /// another's, such as two files sharing a name or one file's name being the
+    /// tail of a longer name.
+    ///
+    /// Writers must reproduce this sharing to re-serialize the package
+    /// byte-for-byte.
+    #[must_use]
+    pub fn uses_interned_names(&self) -> bool {
+        let mut names: Vec<_> = self
+            .files
+            .iter()
+            .map(|file_info| (file_info.name_offset, file_info.raw_name.len() as u64))
+            .collect();
+        names.sort_unstable();
+
+        // The end of the string data of the previous name, including its NUL.
+        let mut end = None;
+        for (offset, len) in names {
+            if end.is_some_and(|end| offset < end) {
+                return true;
+            }
+            end = Some(offset + len + 1);
+        }
+        false
+    }
+
+    /// Returns the files whose stored names are not valid UTF-8, and whose
+    /// `name` therefore differs from the name in the archive.
+    #[must_use]
+    pub fn invalid_utf8_names(&self) -> Vec<&FileInfo> {
+        self.files
+            .iter()
+            .filter(|file_info| std::str::from_utf8(&file_info.raw_name).is_err())
+            .collect()
+    }
+
+    /// Returns the files whose mode, masked with `mask`, equals `value`.
+    ///
+    /// For example, `files_with_mode(0o111, 0o111)` returns the files that are
+    /// executable by everyone.
+    #[must_use]
+    pub fn files_with_mode(&self, mask: u16, value: u16) -> Vec<&FileInfo> {
+        self.files
+            .iter()
+            .filter(|file_info| file_info.mode & mask == value)
+            .collect()
+    }
+
+    /// Reads the data of all of the package's files from `spk` at once.
+    ///
+    /// Everything from the start of the first file's data through to the end of
+    /// the last is read in a single operation, including any gaps between
+    /// files. This suits packages whose data is contiguous and where most of the
+    /// files are needed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn load_all<'p>(&'p self, spk: &SPKFile) -> Result<LoadedPackage<'p>, ReadError> {
+        let start = self.files.iter().map(|f| f.offset).min().unwrap_or(0);
+        let end = self
+            .files
+            .iter()
+            .map(|f| f.offset + f.data_size)
+            .max()
+            .unwrap_or(start);
+
+        let mut data = vec![0; (end - start) as usize];
+        spk.read_exact_at(start, &mut data)?;
+        Ok(LoadedPackage {
+            package: self,
+            base: start,
+            data,
+        })
+    }
+}
+
+/// The data of a package's files, loaded by [`Package::load_all`].
+pub struct LoadedPackage<'p> {
+    package: &'p Package,
+    // The archive offset at which `data` starts.
+    base: u64,
+    data: Vec<u8>,
+}
+
+impl LoadedPackage<'_> {
+    /// Returns the contents of the file named `name`, if the package has one.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn file_bytes(&self, name: &str) -> Option<&[u8]> {
+        let file = self.package.files.iter().find(|f| f.name == name)?;
+        let start = (file.offset - self.base) as usize;
+        Some(&self.data[start..start + file.data_size as usize])
+    }
+}
+
+/// A reader over the stored contents of a file within an archive, returned by
+/// [`SPKFile::open_entry`].
+///
+/// Each read locks the archive's reader only for as long as it takes to seek
+/// to and read the next part of the file.
+pub struct EntryReader<'a> {
+    reader: Arc<Mutex<dyn SeekableReader + 'a>>,
+    offset: u64,
+    data_size: u64,
+    position: u64,
+}
+
+impl std::io::Read for EntryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.data_size - self.position;
+        let len = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let read = {
+            let mut reader = self.reader.lock().unwrap();
+            reader.seek(std::io::SeekFrom::Start(self.offset + self.position))?;
+            reader.read(&mut buf[..len])?
+        };
+        if read == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Archive ended before the end of the file's data",
+            ));
+        }
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+pub(crate) fn is_gzip(path: &Path) -> Result<bool, OpenError> {
+    let mut magic = Vec::with_capacity(compression::GZIP_MAGIC.len());
+    std::fs::File::open(path)?
+        .take(compression::GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    Ok(magic == compression::GZIP_MAGIC)
+}
+
+/// The location of a chunk within an archive, without any interpretation of
+/// its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawChunk {
+    pub magic: [u8; 4],
+    /// The offset of the chunk's header within the archive.
+    pub offset: u64,
+    /// The size of the chunk's header, including its magic.
+    pub header_len: u64,
+    /// The size of the chunk's contents, excluding its header.
+    pub byte_len: u64,
+}
+
+impl RawChunk {
+    fn end(&self) -> u64 {
+        self.offset + self.header_len + self.byte_len
+    }
+}
+
+struct RawChunks<'s, 'a> {
+    file: &'s SPKFile<'a>,
+    position: u64,
+    end: u64,
+}
+
+impl Iterator for RawChunks<'_, '_> {
+    type Item = Result<RawChunk, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.end {
+            return None;
+        }
+
+        let chunk = self.file.read_chunk_header(self.position);
+        match &chunk {
+            // SPK0 chunks are containers, so continue with the chunks inside them.
+            Ok(chunk) if &chunk.magic == b"SPK0" => {
+                self.position = chunk.offset + chunk.header_len;
+                self.end = self.end.min(chunk.end());
+            }
+            Ok(chunk) => self.position = chunk.end(),
+            Err(_) => self.position = self.end,
+        }
+        Some(chunk)
+    }
+}
+
+// Options for the variations on parsing offered by `SPKFile`.
+#[derive(Default)]
+pub(crate) struct ParseOptions<'o> {
+    // Whether to reject non-zero padding.
+    strict: bool,
+    // Called with the kind, offset, and byte length of each chunk.
+    observer: Option<&'o mut dyn FnMut(chunks::ChunkKind, u64, u64)>,
+    // When set, packages that fail to parse are recorded here and skipped.
+    warnings: Option<&'o mut Vec<ParseWarning>>,
+    // When set, only packages of these types are kept.
+    keep: Option<&'o [chunks::PackageType]>,
+    // When set, the chunk offsets of each package kept are recorded here.
+    layouts: Option<&'o mut Vec<PackageLayout>>,
+}
+
+impl ParseOptions<'_> {
+    fn keeps(&self, type_: chunks::PackageType) -> bool {
+        self.keep.is_none_or(|keep| keep.contains(&type_))
+    }
+
+    fn observe(&mut self, kind: chunks::ChunkKind, offset: u64, byte_len: u64) {
+        if let Some(observer) = &mut self.observer {
+            observer(kind, offset, byte_len);
+        }
+    }
+
+    // Records `error` if parsing is lenient, or returns it otherwise.
+    fn warn(&mut self, offset: u64, error: OpenError) -> Result<(), OpenError> {
+        match &mut self.warnings {
+            Some(warnings) => {
+                warnings.push(ParseWarning { offset, error });
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+}
+
+/// The offsets of the chunks making up a package, as returned by
+/// [`SPKFile::parse_with_layout`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PackageLayout {
+    pub spk0_offset: u64,
+    pub sidx_offset: u64,
+    pub strs_offset: u64,
+    pub sdat_offset: u64,
+}
+
+/// A summary of an archive's packages, as returned by [`SPKFile::peek_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    pub packages: Vec<PackageInfo>,
+}
+
+/// A summary of a package, without its files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: Version,
+    pub type_: chunks::PackageType,
+    /// The game ID as a string, or `None` in older updates without one.
+    pub game_id: Option<String>,
+}
+
+impl PackageInfo {
+    fn from_sidx(sidx: &chunks::SIDX) -> Result<Self, OpenError> {
+        Ok(Self {
+            name: CStr::from_bytes_until_nul(&sidx.package_name)?
+                .to_str()?
+                .to_string(),
+            version: Version {
+                major: sidx.major_version,
+                minor: sidx.minor_version,
+                patch: sidx.patch_version,
+            },
+            type_: sidx.package_type,
+            game_id: game_id(sidx.package_id),
+        })
+    }
+}
+
+impl From<&Package> for PackageInfo {
+    fn from(package: &Package) -> Self {
+        Self {
+            name: package.name.clone(),
+            version: package.version,
+            type_: package.type_,
+            game_id: package.game_id.clone(),
+        }
+    }
+}
+
+/// A part of an archive that [`SPKFile::parse_lenient`] skipped because it
+/// failed to parse.
+#[derive(Debug)]
+pub struct ParseWarning {
+    /// The offset of the `SPK0` chunk of the package that failed to parse, or
+    /// of the chunk header that couldn't be read.
+    pub offset: u64,
+    pub error: OpenError,
+}
+
+// Reads a chunk with `read`, attributing any failure to the `chunk` chunk of
+// package `package` at the current position.
+pub(crate) fn read_chunk<R, T>(
+    reader: &mut R,
+    package: Option<usize>,
+    chunk: &'static str,
+    read: impl FnOnce(&mut R) -> binrw::BinResult<T>,
+) -> Result<T, OpenError>
+where
+    R: std::io::Seek,
+{
+    let offset = reader.stream_position()?;
+    read(reader).map_err(|source| OpenError::Chunk {
+        package,
+        chunk,
+        offset,
+        source,
+    })
+}
+
+fn parse_packages<R>(
+    reader: &mut R,
+    options: &mut ParseOptions,
+) -> Result<(Vec<Package>, Layout), OpenError>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let spks = read_chunk(reader, None, "SPKS", PosValue::<chunks::SPKS>::read_le)?;
+    options.observe(chunks::ChunkKind::SPKS, spks.pos, spks.byte_len());
+
+    let mut packages = Vec::new();
+    let mut filtered = 0;
+    let mut offset = reader.stream_position()?;
+    for index in 0..spks.chunk_count as usize {
+        // Without an SPK0 header there's no way to find the next package.
+        let spk0 = match read_chunk(
+            reader,
+            Some(index),
+            "SPK0",
+            PosValue::<chunks::SPK0>::read_le,
+        ) {
+            Ok(spk0) => spk0,
+            Err(error) => {
+                options.warn(offset, error)?;
+                break;
+            }
+        };
+        options.observe(chunks::ChunkKind::SPK0, spk0.pos, spk0.byte_len());
+
+        match parse_package(reader, index, &spk0, options) {
+            Ok(Some(package)) => packages.push(package),
+            Ok(None) => filtered += 1,
+            Err(error) => options.warn(spk0.pos, error)?,
+        }
+
+        // The next SPK0 starts at `offset`.
+        offset = spk0.pos + spk0.offset_to_next();
+        if let Err(error) = reader.seek(std::io::SeekFrom::Start(offset)) {
+            options.warn(offset, error.into())?;
+            break;
+        }
+    }
+
+    let layout = Layout {
+        chunk_count: spks.chunk_count,
+        declared_end: spks.pos + spks.offset_to_next(),
+        parsed_end: offset,
+        filtered,
+    };
+    Ok((packages, layout))
+}
+
+pub(crate) fn parse_package<R>(
+    reader: &mut R,
+    index: usize,
+    spk0: &PosValue<chunks::SPK0>,
+    options: &mut ParseOptions,
+) -> Result<Option<Package>, OpenError>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let package = Some(index);
+    let sidx = read_chunk(reader, package, "SIDX", PosValue::<chunks::SIDX>::read_le)?;
+    options.observe(chunks::ChunkKind::SIDX, sidx.pos, sidx.byte_len.byte_len());
+    // The rest of the package is skipped over by the caller.
+    if !options.keeps(sidx.package_type) {
+        return Ok(None);
+    }
+
+    // SZ64 chunks are optional, so check the magic of the next chunk before
+    // reading one.
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    reader.seek(std::io::SeekFrom::Current(-4))?;
+    let sz64 = if &magic == b"SZ64" {
+        let sz64 = read_chunk(reader, package, "SZ64", PosValue::<chunks::SZ64>::read_le)?;
+        options.observe(chunks::ChunkKind::SZ64, sz64.pos, u64::from(sz64.byte_len));
+        Some(sz64.val)
+    } else {
+        None
+    };
+
+    let strs = read_chunk(reader, package, "STRS", PosValue::<chunks::STRS>::read_le)?;
+    options.observe(chunks::ChunkKind::STRS, strs.pos, u64::from(strs.byte_len));
+    let mut files = Vec::new();
+    loop {
+        let file_info = read_chunk(reader, package, "FINF, FI64, or FEND", |reader| {
+            PosValue::<chunks::FileInfo>::read_le_args(reader, (strs.pos + 8,))
+        })?;
+        options.observe(
+            file_info.kind(),
+            file_info.pos,
+            u64::from(file_info.byte_len()),
+        );
+        if let chunks::FileInfo::FEND(_) = file_info.val {
+            break;
+        }
+
+        let padding = options.strict.then(|| file_info.val.padding());
+        let is_64bit = file_info.kind() == chunks::ChunkKind::FI64;
+        let offset = file_info.pos;
+        let Ok(file_info) = chunks::FI64::try_from(file_info.val) else {
+            Err(OpenError::UnexpectedChunk { offset })?
+        };
+        if let Some(padding) = padding
+            && padding.iter().any(|&b| b != 0)
+        {
+            Err(OpenError::NonZeroPadding {
+                file: file_info.filename.to_string(),
+                padding,
+            })?;
+        }
+        files.push(FileInfo {
+            name: file_info.filename.to_string(),
+            raw_name: file_info.filename.0,
+            name_offset: file_info.name_offset,
+            size: file_info.file_size,
+            offset: file_info.data_offset,
+            stored_offset: file_info.data_offset,
+            is_64bit,
+            data_size: file_info.data_size,
+            mode: file_info.mode,
+            hmac: file_info.data_hmac,
+            md5: file_info.data_md5,
+        });
+    }
+
+    let sdat = read_chunk(reader, package, "SDAT", PosValue::<chunks::SDAT>::read_le)?;
+    options.observe(chunks::ChunkKind::SDAT, sdat.pos, sdat.byte_len());
+    // Both the data offset and the SDAT chunk's position come from the
+    // archive, so their sum can overflow if it is corrupt or crafted.
+    let data_start = sdat.pos + sdat.header_size();
+    for file in &mut files {
+        file.offset =
+            file.offset
+                .checked_add(data_start)
+                .ok_or_else(|| OpenError::InvalidOffset {
+                    file: file.name.clone(),
+                    offset: file.offset,
+                })?;
+    }
+    if let Some(layouts) = &mut options.layouts {
+        layouts.push(PackageLayout {
+            spk0_offset: spk0.pos,
+            sidx_offset: sidx.pos,
+            strs_offset: strs.pos,
+            sdat_offset: sdat.pos,
+        });
+    }
+
+    let PackageInfo {
+        name,
+        version,
+        type_,
+        game_id,
+    } = PackageInfo::from_sidx(&sidx)?;
+    Ok(Some(Package {
+        name,
+        package_id: sidx.package_id,
+        game_id,
+        version,
+        type_,
+        files,
+        offset: spk0.pos,
+        sidx_offset: sidx.pos,
+        strs_offset: strs.pos,
+        strs_byte_len: strs.byte_len,
+        uncompressed_size: sz64.map(|sz64| sz64.uncompressed_size),
+        raw_sidx_trailer: sidx.unknown_b,
+    }))
 }
 
 impl<'a> SPKFile<'a> {
-    pub fn parse<R>(mut reader: R) -> Result<Self, OpenError>
+    pub fn parse<R>(reader: R) -> Result<Self, OpenError>
     where
         R: std::io::Read + std::io::Seek + Send + 'a,
     {
-        let spks = chunks::SPKS::read_le(&mut reader)?;
+        Self::parse_inner(reader, ParseOptions::default())
+    }
 
-        let mut packages = Vec::new();
-        for _ in 0..spks.chunk_count {
-            let spk0 = PosValue::<chunks::SPK0>::read_le(&mut reader)?;
-            let sidx = chunks::SIDX::read_le(&mut reader)?;
-
-            // TODO: It's unclear what this is used for.
-            let _ = chunks::SZ64::read_le(&mut reader);
-
-            let strs = PosValue::<chunks::STRS>::read_le(&mut reader)?;
-            let mut files = Vec::new();
-            loop {
-                let file_info =
-                    PosValue::<chunks::FileInfo>::read_le_args(&mut reader, (strs.pos + 8,))?;
-                if let chunks::FileInfo::FEND(_) = file_info.val {
-                    break;
-                }
+    /// Parses an archive from any seekable reader, such as an already open
+    /// [`File`](std::fs::File) or a [`Cursor`] over an in-memory buffer.
+    ///
+    /// This is the same as [`SPKFile::parse`]. Nothing but `reader` is
+    /// needed, so unlike [`SPKFile::open`] no path is involved.
+    pub fn from_reader<R>(reader: R) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        Self::parse(reader)
+    }
 
-                let file_info: chunks::FI64 = file_info.val.try_into().unwrap();
-                files.push(FileInfo {
-                    name: file_info.filename.to_string(),
-                    size: file_info.file_size,
-                    offset: file_info.data_offset,
-                    data_size: file_info.data_size,
-                    mode: file_info.mode,
-                    hmac: file_info.data_hmac,
-                    md5: file_info.data_md5,
-                });
-            }
+    /// Parses an archive that starts `skip_bytes` into `reader`, such as one
+    /// preceded by a signature block.
+    ///
+    /// Offsets, including [`Package::offset`] and the offsets reported by
+    /// [`SPKFile::package_chunks`], remain relative to the start of the
+    /// archive's `SPKS` chunk rather than to the start of `reader`.
+    pub fn parse_skipping<R>(mut reader: R, skip_bytes: u64) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        reader.seek(std::io::SeekFrom::Start(skip_bytes))?;
+        Self::parse(Skipped {
+            inner: reader,
+            base: skip_bytes,
+        })
+    }
 
-            let sdat = PosValue::<chunks::SDAT>::read_le(&mut reader)?;
-            for file in &mut files {
-                file.offset += sdat.pos + sdat.header_size();
-            }
+    /// Parses an archive like [`SPKFile::parse`], calling `observer` with the
+    /// kind, offset, and declared length of each chunk as it is read.
+    ///
+    /// The length is the chunk's byte length as stored in its header, so it
+    /// excludes the header itself. Chunks are reported in the order they are
+    /// read, which is the order they appear in the archive.
+    pub fn parse_with_chunk_observer<R>(
+        reader: R,
+        mut observer: impl FnMut(chunks::ChunkKind, u64, u64),
+    ) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        Self::parse_inner(
+            reader,
+            ParseOptions {
+                observer: Some(&mut observer),
+                ..ParseOptions::default()
+            },
+        )
+    }
 
-            let package = Package {
-                name: CStr::from_bytes_until_nul(&sidx.package_name)?
-                    .to_str()?
-                    .to_string(),
-                version: (sidx.major_version, sidx.minor_version, sidx.patch_version),
-                type_: sidx.package_type,
-                files,
-            };
-            packages.push(package);
+    /// Parses an archive like [`SPKFile::parse`], but skips packages that fail
+    /// to parse rather than failing entirely.
+    ///
+    /// A warning is returned for each package that was skipped. Parsing
+    /// continues with the next package, found using the length of the failed
+    /// package's `SPK0` chunk, so this recovers what it can from partially
+    /// corrupt or truncated archives.
+    pub fn parse_lenient<R>(reader: R) -> (Self, Vec<ParseWarning>)
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        let mut warnings = Vec::new();
+        let result = Self::parse_inner(
+            reader,
+            ParseOptions {
+                warnings: Some(&mut warnings),
+                ..ParseOptions::default()
+            },
+        );
+        let file = result.expect("lenient parsing records errors as warnings");
+        (file, warnings)
+    }
+
+    /// Parses an archive like [`SPKFile::parse`], but only keeps packages whose
+    /// type is one of `keep`.
+    ///
+    /// Every package's `SIDX` chunk is still read to find its type, but the
+    /// files of other packages are skipped over without being read.
+    pub fn parse_filtered<R>(reader: R, keep: &[chunks::PackageType]) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        Self::parse_inner(
+            reader,
+            ParseOptions {
+                keep: Some(keep),
+                ..ParseOptions::default()
+            },
+        )
+    }
 
-            // The next SPK0 starts at `offset`.
-            let offset = spk0.pos + spk0.offset_to_next();
-            reader.seek(std::io::SeekFrom::Start(offset))?;
+    /// Parses an archive like [`SPKFile::parse`], also returning the offsets of
+    /// the `SPK0`, `SIDX`, `STRS`, and `SDAT` chunks of each package.
+    ///
+    /// Layouts are returned in the same order as the packages.
+    pub fn parse_with_layout<R>(reader: R) -> Result<(Self, Vec<PackageLayout>), OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        let mut layouts = Vec::new();
+        let file = Self::parse_inner(
+            reader,
+            ParseOptions {
+                layouts: Some(&mut layouts),
+                ..ParseOptions::default()
+            },
+        )?;
+        Ok((file, layouts))
+    }
+
+    /// Parses an archive like [`SPKFile::parse`], but validates parts of the
+    /// format that are normally skipped over.
+    ///
+    /// The padding within each `FINF` and `FI64` chunk must be zero, or parsing
+    /// fails with [`OpenError::NonZeroPadding`]. Non-zero padding may indicate
+    /// a misparse or an undocumented field.
+    pub fn parse_strict<R>(reader: R) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        Self::parse_inner(
+            reader,
+            ParseOptions {
+                strict: true,
+                ..ParseOptions::default()
+            },
+        )
+    }
+
+    fn parse_inner<R>(mut reader: R, mut options: ParseOptions) -> Result<Self, OpenError>
+    where
+        R: std::io::Read + std::io::Seek + Send + 'a,
+    {
+        let start = reader.stream_position();
+
+        // Reads are checked against the archive's length where the reader can
+        // seek to its end, so that a truncated archive is reported as such.
+        let mut archive_len = None;
+        if let Ok(start) = start {
+            archive_len = reader.seek(std::io::SeekFrom::End(0)).ok();
+            reader.seek(std::io::SeekFrom::Start(start))?;
         }
 
+        let (packages, layout) = match parse_packages(&mut reader, &mut options) {
+            Ok((packages, layout)) => (packages, Some(layout)),
+            // When lenient, this is only reached if the SPKS chunk is unreadable.
+            Err(error) => match &mut options.warnings {
+                Some(warnings) => {
+                    warnings.push(ParseWarning {
+                        offset: start.unwrap_or_default(),
+                        error,
+                    });
+                    (Vec::new(), None)
+                }
+                None => Err(error)?,
+            },
+        };
+
         Ok(Self {
             packages,
             reader: Arc::new(Mutex::new(reader)),
+            path: None,
+            archive_len,
+            index: None,
+            layout,
         })
     }
 
+    /// Parses an archive from `reader`, checking reads against the length it
+    /// reports rather than the length found by seeking to its end.
+    pub fn parse_len_reader<R>(reader: R) -> Result<Self, OpenError>
+    where
+        R: LenReader + 'a,
+    {
+        let archive_len = reader.total_len();
+        let mut file = Self::parse(reader)?;
+        file.archive_len = Some(archive_len);
+        Ok(file)
+    }
+
     pub fn open(path: &Path) -> Result<Self, OpenError> {
         if std::fs::metadata(path)?.is_dir() {
-            let paths = glob::glob(&format!("{}/*.000", path.display()))?;
-            let paths: Vec<_> = paths.filter_map(Result::ok).collect();
-            if paths.len() != 1 {
-                Err(OpenError::DirectoryDoesNotContainSplitSPK)?;
+            let paths = Self::list_archives(path)?;
+            match paths.len() {
+                0 => Err(OpenError::DirectoryDoesNotContainSplitSPK)?,
+                1 => {}
+                _ => return Err(OpenError::DirectoryContainsMultipleSplitSPKs(paths)),
             }
             return Self::open_split_squashed(&paths[0]);
         }
 
         match path.extension().and_then(OsStr::to_str) {
+            Some("spk") if is_gzip(path)? => Self::open_gzip(path),
             Some("spk") => Self::open_single_file(path),
+            Some("gz") => Self::open_gzip(path),
             Some("000") => Self::open_split_squashed(path),
             None | Some(_) => Err(OpenError::UnknownFileType)?,
         }
     }
 
+    /// Lists the first parts of the split updates in `dir`, the files with a
+    /// `.000` extension, sorted by name.
+    pub fn list_archives(dir: &Path) -> Result<Vec<PathBuf>, OpenError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension() == Some(OsStr::new("000")) && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Opens the split update in `dir` whose first part is `{stem}.000`, for
+    /// directories that contain more than one update.
+    pub fn open_named(dir: &Path, stem: &str) -> Result<Self, OpenError> {
+        Self::open_split_squashed(&dir.join(format!("{stem}.000")))
+    }
+
+    /// Reads the packages of the archive at `path` without keeping it open.
+    ///
+    /// This suits tools that only list an archive's contents: the underlying
+    /// reader is closed before returning, so its files can't be read.
+    pub fn list_only(path: &Path) -> Result<Vec<Package>, OpenError> {
+        Ok(Self::open(path)?.packages)
+    }
+
+    /// Summarizes the packages of the archive at `path` without parsing their
+    /// files.
+    ///
+    /// For an uncompressed .spk file, only the `SPKS` chunk and each package's
+    /// `SPK0` and `SIDX` chunks are read, skipping over everything else, which
+    /// is much faster than opening a large archive. Other archives must be
+    /// decompressed or extracted first, so they are opened in full.
+    pub fn peek_info(path: &Path) -> Result<ArchiveInfo, OpenError> {
+        if path.extension() != Some(OsStr::new("spk")) || is_gzip(path)? {
+            return Ok(ArchiveInfo {
+                packages: Self::list_only(path)?
+                    .iter()
+                    .map(PackageInfo::from)
+                    .collect(),
+            });
+        }
+
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let spks = read_chunk(&mut reader, None, "SPKS", PosValue::<chunks::SPKS>::read_le)?;
+        let mut packages = Vec::new();
+        for index in 0..spks.chunk_count as usize {
+            let package = Some(index);
+            let spk0 = read_chunk(
+                &mut reader,
+                package,
+                "SPK0",
+                PosValue::<chunks::SPK0>::read_le,
+            )?;
+            let sidx = read_chunk(&mut reader, package, "SIDX", chunks::SIDX::read_le)?;
+            packages.push(PackageInfo::from_sidx(&sidx)?);
+            reader.seek(std::io::SeekFrom::Start(spk0.pos + spk0.offset_to_next()))?;
+        }
+
+        Ok(ArchiveInfo { packages })
+    }
+
+    /// Opens the archive at `path`, failing with
+    /// [`OpenError::UnexpectedGameId`] unless one of its packages has the game
+    /// ID `game_id`.
+    pub fn open_expecting(path: &Path, game_id: [u8; 3]) -> Result<Self, OpenError> {
+        let file = Self::open(path)?;
+        if !file
+            .packages
+            .iter()
+            .any(|package| package.package_id == game_id)
+        {
+            Err(OpenError::UnexpectedGameId {
+                expected: game_id,
+                found: file
+                    .packages
+                    .iter()
+                    .map(|package| package.package_id)
+                    .collect(),
+            })?;
+        }
+        Ok(file)
+    }
+
     pub fn open_single_file(path: &Path) -> Result<Self, OpenError> {
         let file = std::fs::File::open(path)?;
-        let reader = Box::new(file);
-        Self::parse(reader)
+        Self::parse_len_reader(file)
+    }
+
+    /// Opens a single .spk file by mapping it into memory, so that reads copy
+    /// from the mapping rather than seeking and reading the file.
+    ///
+    /// The file must not be modified while the archive is open.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(path: &Path) -> Result<Self, OpenError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The caller is responsible for the file not being modified
+        // while it is mapped, as documented above.
+        let mapping = unsafe { memmap2::Mmap::map(&file)? };
+        Self::parse_len_reader(Cursor::new(mapping))
+    }
+
+    /// Opens a gzip-compressed .spk file.
+    ///
+    /// Parsing requires seeking, so the archive is decompressed into memory in
+    /// its entirety.
+    pub fn open_gzip(path: &Path) -> Result<Self, OpenError> {
+        let mut data = Vec::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(path)?).read_to_end(&mut data)?;
+        Self::parse_len_reader(Cursor::new(data))
+    }
+
+    /// Opens a single .spk file such that each read uses its own file handle.
+    ///
+    /// Reads from files opened this way don't share a reader, so reads from
+    /// multiple threads run concurrently rather than contending on a lock.
+    pub fn with_parallel_handles(path: &Path) -> Result<Self, OpenError> {
+        let mut file = Self::open_single_file(path)?;
+        file.path = Some(path.to_path_buf());
+        Ok(file)
     }
 
     pub fn open_split_squashed(path: &Path) -> Result<Self, OpenError> {
         let spk_file_data = squashed::extract_spk_file(path)?;
-        Self::parse(Cursor::new(spk_file_data))
+        Self::parse_len_reader(Cursor::new(spk_file_data))
     }
 
-    #[allow(clippy::cast_possible_truncation)]
+    /// Opens a split update like [`SPKFile::open_split_squashed`], but without
+    /// buffering its SquashFS image or the .spk file within it in memory.
+    ///
+    /// The .spk file is instead extracted to a temporary file, which is read
+    /// from as needed, so peak memory use doesn't grow with the size of the
+    /// update. This needs enough temporary disk space to hold the .spk file.
+    pub fn open_split_squashed_streaming(path: &Path) -> Result<Self, OpenError> {
+        Self::parse_len_reader(squashed::spk_file_reader(path, None)?)
+    }
+
+    /// Opens a split update, using `selector` to choose which file within its
+    /// SquashFS file system is the .spk file.
+    ///
+    /// The first file for which `selector` returns true is opened. This is
+    /// useful when the file system contains more than one candidate file.
+    pub fn open_split_squashed_with(
+        path: &Path,
+        selector: impl Fn(&squashed::SquashEntry) -> bool,
+    ) -> Result<Self, OpenError> {
+        let spk_file_data = squashed::extract_spk_file_with(path, Some(&selector))?;
+        Self::parse_len_reader(Cursor::new(spk_file_data))
+    }
+
+    /// Reads the stored contents of `file`.
+    ///
+    /// Reads that share a reader hold its lock only while seeking and reading,
+    /// so concurrent reads of different files are serialized on I/O alone.
     pub fn read(&self, file: &FileInfo) -> Result<Vec<u8>, ReadError> {
-        let mut buf = vec![0; file.data_size as usize];
+        let mut buf = Vec::new();
+        self.read_into(file, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the stored contents of each of `files` in parallel, returning the
+    /// results in the same order.
+    ///
+    /// Reads only run concurrently if the archive was opened with
+    /// [`SPKFile::with_parallel_handles`], which gives each read its own file
+    /// handle. Otherwise they take turns on the shared reader.
+    #[must_use]
+    pub fn read_parallel(&self, files: &[&FileInfo]) -> Vec<Result<Vec<u8>, ReadError>> {
+        files.par_iter().map(|file| self.read(file)).collect()
+    }
+
+    /// Reads the stored contents of `file` into `buf`, replacing whatever it
+    /// held.
+    ///
+    /// Reusing one buffer across many files avoids allocating for each file
+    /// once the buffer has grown to the size of the largest.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_into(&self, file: &FileInfo, buf: &mut Vec<u8>) -> Result<(), ReadError> {
+        buf.clear();
+        buf.resize(file.data_size as usize, 0);
+        self.read_exact_at(file.offset, buf)
+    }
+
+    /// Returns a reader over the stored contents of `file`, for reading large
+    /// files without buffering them in their entirety.
+    ///
+    /// The reader yields only the file's own data, reaching the end of its
+    /// input at the end of the file.
+    pub fn open_entry(&self, file: &FileInfo) -> std::io::Result<EntryReader<'a>> {
+        Ok(EntryReader {
+            reader: Arc::clone(&self.reader),
+            offset: file.offset,
+            data_size: file.data_size,
+            position: 0,
+        })
+    }
+
+    /// Copies the stored contents of `file` to `writer` without buffering the
+    /// whole file, returning the number of bytes copied.
+    ///
+    /// Fails with [`ReadError::Truncated`] if the archive ends before all of
+    /// the file's data. Where the archive's length is known, this is checked
+    /// before anything is written to `writer`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_to(
+        &self,
+        file: &FileInfo,
+        writer: &mut impl std::io::Write,
+    ) -> Result<u64, ReadError> {
+        self.check_bounds(file.offset, file.data_size)
+            .map_err(|_| ReadError::Truncated {
+                expected: file.data_size,
+                copied: 0,
+            })?;
+
+        let mut buf = vec![0; READ_BLOCK_SIZE.min(file.data_size) as usize];
+        let mut position = 0;
+        while position < file.data_size {
+            let block = &mut buf[..READ_BLOCK_SIZE.min(file.data_size - position) as usize];
+            match self.read_exact_at(file.offset + position, block) {
+                Err(ReadError::IOError(error))
+                    if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    Err(ReadError::Truncated {
+                        expected: file.data_size,
+                        copied: position,
+                    })?;
+                }
+                result => result?,
+            }
+            writer.write_all(block)?;
+            position += block.len() as u64;
+        }
+
+        Ok(position)
+    }
+
+    /// Reads the target of the symlink `file`, which is stored as its contents.
+    ///
+    /// Fails with [`ReadError::NotSymlink`] if `file` isn't a symlink.
+    pub fn read_symlink(&self, file: &FileInfo) -> Result<PathBuf, ReadError> {
+        if file.file_type() != EntryKind::Symlink {
+            Err(ReadError::NotSymlink(file.name.clone()))?;
+        }
+        Ok(PathBuf::from(String::from_utf8(self.read(file)?)?))
+    }
+
+    /// Reads up to `len` bytes of `file` starting `start` bytes into its
+    /// contents, stopping early at the end of the file.
+    ///
+    /// Fails with [`ReadError::InvalidRange`] if `start` is past the end of the
+    /// file.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_range(&self, file: &FileInfo, start: u64, len: u64) -> Result<Vec<u8>, ReadError> {
+        if start > file.data_size {
+            Err(ReadError::InvalidRange {
+                start,
+                data_size: file.data_size,
+            })?;
+        }
+
+        let len = len.min(file.data_size - start);
+        let mut buf = vec![0; len as usize];
+        self.read_exact_at(file.offset + start, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the last `n` bytes of `file`, or all of it if it is shorter.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_tail(&self, file: &FileInfo, n: u64) -> Result<Vec<u8>, ReadError> {
+        let n = n.min(file.data_size);
+        let mut buf = vec![0; n as usize];
+        self.read_exact_at(file.offset + file.data_size - n, &mut buf)?;
+        Ok(buf)
+    }
+
+    // Checks that `data_size` bytes at `offset` lie within the archive, if its
+    // length is known.
+    pub(crate) fn check_bounds(&self, offset: u64, data_size: u64) -> Result<(), ReadError> {
+        if let Some(archive_len) = self.archive_len
+            && offset
+                .checked_add(data_size)
+                .is_none_or(|end| end > archive_len)
+        {
+            Err(ReadError::OutOfBounds {
+                offset,
+                data_size,
+                archive_len,
+            })?;
+        }
+        Ok(())
+    }
+
+    // All reads of file data go through here. Callers allocate `buf` before
+    // calling and process its contents afterwards, so the shared reader's lock
+    // is only held for the seek and read themselves.
+    pub(crate) fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), ReadError> {
+        self.check_bounds(offset, buf.len() as u64)?;
+
+        if let Some(path) = &self.path {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(std::io::SeekFrom::Start(offset))?;
+            file.read_exact(buf)?;
+            return Ok(());
+        }
+
         let mut reader = self.reader.lock().unwrap();
-        reader.seek(std::io::SeekFrom::Start(file.offset))?;
-        reader.read_exact(&mut buf)?;
+        reader.seek(std::io::SeekFrom::Start(offset))?;
+        reader.read_exact(buf)?;
+        Ok(())
+    }
+
+    /// Returns whether the contents of `file` consist entirely of zero bytes.
+    ///
+    /// The data is read a block at a time, stopping at the first non-zero byte,
+    /// so the file is never held in memory as a whole.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn is_zero_filled(&self, file: &FileInfo) -> Result<bool, ReadError> {
+        let mut buf = vec![0; READ_BLOCK_SIZE.min(file.data_size) as usize];
+        let mut position = 0;
+        while position < file.data_size {
+            let block = &mut buf[..READ_BLOCK_SIZE.min(file.data_size - position) as usize];
+            self.read_exact_at(file.offset + position, block)?;
+            if block.iter().any(|&b| b != 0) {
+                return Ok(false);
+            }
+            position += block.len() as u64;
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the length of `file`'s contents once decompressed.
+    ///
+    /// Files whose stored size matches their `size` are not compressed, and
+    /// their stored size is returned as-is.
+    pub fn decompressed_len(&self, file: &FileInfo) -> Result<u64, ReadError> {
+        if file.data_size == file.size {
+            return Ok(file.data_size);
+        }
+
+        let data = self.read(file)?;
+        let mut decoder = compression::decoder(&data)?;
+        Ok(std::io::copy(&mut decoder, &mut std::io::sink())?)
+    }
+
+    /// Checks that `file`'s contents decompress to exactly `size` bytes,
+    /// failing with [`ReadError::DecompressedSizeMismatch`] holding the actual
+    /// length otherwise.
+    ///
+    /// This catches corrupt compressed data that decodes to the wrong length.
+    pub fn verify_decompressed_size(&self, file: &FileInfo) -> Result<(), ReadError> {
+        let actual = self.decompressed_len(file)?;
+        if actual != file.size {
+            Err(ReadError::DecompressedSizeMismatch {
+                expected: file.size,
+                actual,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Reads the contents of `file`, decompressing them if they are stored
+    /// compressed.
+    ///
+    /// Files whose stored size differs from their `size` are decompressed
+    /// with the codec identified by their header, failing with
+    /// [`ReadError::UnsupportedCompression`] if it isn't recognized or its
+    /// feature isn't enabled, and with
+    /// [`ReadError::DecompressedSizeMismatch`] unless exactly `size` bytes
+    /// result. Decompression stops just past `size` bytes, so a corrupt file
+    /// can't decompress to an unbounded length.
+    pub fn read_decompressed(&self, file: &FileInfo) -> Result<Vec<u8>, ReadError> {
+        let data = self.read(file)?;
+        if file.data_size == file.size {
+            return Ok(data);
+        }
+
+        let mut contents = Vec::new();
+        compression::decoder(&data)?
+            .take(file.size.saturating_add(1))
+            .read_to_end(&mut contents)?;
+        if contents.len() as u64 != file.size {
+            Err(ReadError::DecompressedSizeMismatch {
+                expected: file.size,
+                actual: contents.len() as u64,
+            })?;
+        }
+        Ok(contents)
+    }
+
+    /// Finds the file named `path` within the package named `package`.
+    ///
+    /// This scans the package's files unless [`SPKFile::build_index`] has been
+    /// called, making repeated lookups cheaper.
+    #[must_use]
+    pub fn find(&self, package: &str, path: &str) -> Option<&FileInfo> {
+        let package = self.packages.iter().find(|p| p.name == package)?;
+        match &self.index {
+            Some(index) => index
+                .get(&(package.name.clone(), path.to_string()))
+                .and_then(|&i| package.files.get(i))
+                // The index is stale if `packages` has been modified since.
+                .filter(|file_info| file_info.name == path),
+            None => package
+                .files
+                .iter()
+                .find(|file_info| file_info.name == path),
+        }
+    }
+
+    /// Returns the package named `name`, ignoring ASCII case.
+    #[must_use]
+    pub fn get_package_ci(&self, name: &str) -> Option<&Package> {
+        self.packages
+            .iter()
+            .find(|package| package.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Finds the file named `path` within the package named `package`, like
+    /// [`SPKFile::find`] but ignoring ASCII case in both names.
+    ///
+    /// This always scans the package's files, as the index built by
+    /// [`SPKFile::build_index`] is case-sensitive.
+    #[must_use]
+    pub fn find_ci(&self, package: &str, path: &str) -> Option<&FileInfo> {
+        self.get_package_ci(package)?
+            .files
+            .iter()
+            .find(|file_info| file_info.name.eq_ignore_ascii_case(path))
+    }
+
+    /// Builds an index of the archive's files so that [`SPKFile::find`] doesn't
+    /// need to scan them.
+    ///
+    /// The index must be rebuilt if `packages` is modified.
+    pub fn build_index(&mut self) {
+        self.index =
+            Some(
+                self.packages
+                    .iter()
+                    .flat_map(|package| {
+                        package.files.iter().enumerate().map(|(i, file_info)| {
+                            ((package.name.clone(), file_info.name.clone()), i)
+                        })
+                    })
+                    .collect(),
+            );
+    }
+
+    /// Returns the files whose installed paths match the glob `pattern`, such
+    /// as `games/skk_le/**/*.png`.
+    ///
+    /// Paths are matched without their leading `/`, and `*` doesn't match
+    /// across directories while `**` does.
+    pub fn glob(&self, pattern: &str) -> Result<Vec<&FileInfo>, OpenError> {
+        let pattern = glob::Pattern::new(pattern)?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        Ok(self
+            .iter_files()
+            .filter(|(package, file_info)| {
+                let path = file_info.full_path(package);
+                pattern.matches_with(path.trim_start_matches('/'), options)
+            })
+            .map(|(_, file_info)| file_info)
+            .collect())
+    }
+
+    /// Iterates over the packages of type `type_`.
+    pub fn packages_by_type(&self, type_: chunks::PackageType) -> impl Iterator<Item = &Package> {
+        self.packages
+            .iter()
+            .filter(move |package| package.type_ == type_)
+    }
+
+    /// Iterates over the files of every package, along with the package each
+    /// file belongs to.
+    pub fn iter_files(&self) -> impl Iterator<Item = (&Package, &FileInfo)> {
+        self.packages.iter().flat_map(|package| {
+            package
+                .files
+                .iter()
+                .map(move |file_info| (package, file_info))
+        })
+    }
+
+    /// Iterates over the files of every package along with their stored
+    /// contents, reading each file only once the iterator reaches it.
+    ///
+    /// Each read holds the reader's lock only while it runs, as with
+    /// [`SPKFile::read`], so reads from other threads can interleave with
+    /// the iteration.
+    pub fn read_iter(&self) -> impl Iterator<Item = Result<(&FileInfo, Vec<u8>), ReadError>> {
+        self.iter_files()
+            .map(|(_, file_info)| Ok((file_info, self.read(file_info)?)))
+    }
+
+    /// Sorts the files of every package by name, as [`Package::sort_files`]
+    /// does, rebuilding the index if one was built.
+    pub fn sort_all(&mut self) {
+        for package in &mut self.packages {
+            package.sort_files();
+        }
+        if self.index.is_some() {
+            self.build_index();
+        }
+    }
+
+    /// Computes a SHA-256 hash of the installed path and MD5 of every file in
+    /// the archive.
+    ///
+    /// Files are hashed in order of path, so archives with the same contents
+    /// have the same fingerprint however their packages and files are ordered
+    /// or laid out.
+    #[must_use]
+    pub fn content_fingerprint(&self) -> [u8; 32] {
+        let mut files: Vec<_> = self
+            .iter_files()
+            .map(|(package, file_info)| (file_info.full_path(package), file_info.md5))
+            .collect();
+        files.sort();
+
+        let mut sha256 = sha2::Sha256::new();
+        for (path, md5) in files {
+            sha256.update((path.len() as u64).to_le_bytes());
+            sha256.update(path);
+            sha256.update(md5);
+        }
+        sha256.finalize().into()
+    }
+
+    /// Returns the total installed size of the files in all packages.
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.packages.iter().map(Package::total_size).sum()
+    }
+
+    /// Finds the files whose data overlaps within each package, as
+    /// [`Package::check_overlaps`] does, returning the index of the package
+    /// along with each pair of file indices.
+    #[must_use]
+    pub fn check_overlaps(&self) -> Vec<(usize, usize, usize)> {
+        self.packages
+            .iter()
+            .enumerate()
+            .flat_map(|(p, package)| {
+                package
+                    .check_overlaps()
+                    .into_iter()
+                    .map(move |(a, b)| (p, a, b))
+            })
+            .collect()
+    }
+
+    /// Returns every file, across all packages, whose MD5 is `digest`.
+    #[must_use]
+    pub fn find_by_md5(&self, digest: &[u8; 16]) -> Vec<(&Package, &FileInfo)> {
+        self.find_files(|file_info| &file_info.md5 == digest)
+    }
+
+    /// Returns every file, across all packages, whose HMAC is `digest`.
+    #[must_use]
+    pub fn find_by_hmac(&self, digest: &[u8; 20]) -> Vec<(&Package, &FileInfo)> {
+        self.find_files(|file_info| &file_info.hmac == digest)
+    }
+
+    fn find_files(&self, predicate: impl Fn(&FileInfo) -> bool) -> Vec<(&Package, &FileInfo)> {
+        self.iter_files()
+            .filter(|(_, file_info)| predicate(file_info))
+            .collect()
+    }
+
+    /// Groups the files in all packages by their MD5, returning only the groups
+    /// of files that share an MD5 with at least one other file.
+    ///
+    /// No file data is read, so this relies on the stored digests.
+    #[must_use]
+    pub fn duplicates(&self) -> HashMap<[u8; 16], Vec<(&Package, &FileInfo)>> {
+        let mut groups: HashMap<_, Vec<_>> = HashMap::new();
+        for (package, file_info) in self.iter_files() {
+            groups
+                .entry(file_info.md5)
+                .or_default()
+                .push((package, file_info));
+        }
+        groups.retain(|_, files| files.len() > 1);
+        groups
+    }
+
+    /// Counts the files in all packages by their extension.
+    ///
+    /// Only the final extension is considered, so `textures.tar.gz` is counted
+    /// under `gz`. Files without an extension, including dotfiles such as
+    /// `.profile`, are counted under the empty string.
+    #[must_use]
+    pub fn counts_by_extension(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for file_info in self.packages.iter().flat_map(|package| &package.files) {
+            let extension = Path::new(&file_info.name)
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            *counts.entry(extension).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Counts the files in all packages by their mode.
+    #[must_use]
+    pub fn distinct_modes(&self) -> BTreeMap<u16, usize> {
+        let mut counts = BTreeMap::new();
+        for file_info in self.packages.iter().flat_map(|package| &package.files) {
+            *counts.entry(file_info.mode).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Checks that the packages parsed match what the archive's `SPKS` chunk
+    /// declares, catching truncated or padded archives.
+    ///
+    /// The chunk's length is checked against the archive's length when that
+    /// is known, and then its package count and length are checked against
+    /// the packages that were parsed. The first discrepancy found is returned.
+    pub fn validate_layout(&self) -> Result<(), LayoutError> {
+        let layout = self.layout.ok_or(LayoutError::Unknown)?;
+
+        if let Some(archive_len) = self.archive_len
+            && layout.declared_end > archive_len
+        {
+            Err(LayoutError::Truncated {
+                declared_end: layout.declared_end,
+                archive_len,
+            })?;
+        }
+        let parsed = self.packages.len() + layout.filtered;
+        if parsed != layout.chunk_count as usize {
+            Err(LayoutError::ChunkCountMismatch {
+                declared: layout.chunk_count,
+                parsed,
+            })?;
+        }
+        if layout.parsed_end != layout.declared_end {
+            Err(LayoutError::LengthMismatch {
+                declared_end: layout.declared_end,
+                parsed_end: layout.parsed_end,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Walks the chunks making up `package`, starting with its `SPK0` chunk
+    /// and followed by each of the chunks contained within it.
+    pub fn package_chunks<'s>(
+        &'s self,
+        package: &Package,
+    ) -> impl Iterator<Item = Result<RawChunk, ReadError>> + use<'s, 'a> {
+        RawChunks {
+            file: self,
+            position: package.offset,
+            end: u64::MAX,
+        }
+    }
+
+    /// Returns the raw bytes of `package`'s `SIDX` chunk, including its header.
+    pub fn raw_sidx(&self, package: &Package) -> Result<Vec<u8>, ReadError> {
+        self.read_raw_chunk(package.sidx_offset)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn read_raw_chunk(&self, offset: u64) -> Result<Vec<u8>, ReadError> {
+        let chunk = self.read_chunk_header(offset)?;
+        let mut buf = vec![0; (chunk.header_len + chunk.byte_len) as usize];
+        self.read_exact_at(offset, &mut buf)?;
         Ok(buf)
     }
+
+    fn read_chunk_header(&self, offset: u64) -> Result<RawChunk, ReadError> {
+        let mut header = [0; 8];
+        self.read_exact_at(offset, &mut header)?;
+        let magic = [header[0], header[1], header[2], header[3]];
+
+        // A 32-bit length of 0xffffffff indicates that a 64-bit length follows.
+        let byte_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if byte_len == u32::MAX {
+            let mut byte_len = [0; 8];
+            self.read_exact_at(offset + 8, &mut byte_len)?;
+            return Ok(RawChunk {
+                magic,
+                offset,
+                header_len: 16,
+                byte_len: u64::from_le_bytes(byte_len),
+            });
+        }
+
+        Ok(RawChunk {
+            magic,
+            offset,
+            header_len: 8,
+            byte_len: u64::from(byte_len),
+        })
+    }
+}
+
+impl SPKFile<'static> {
+    /// Parses an archive held in memory.
+    ///
+    /// The archive takes ownership of `data`, so the result isn't tied to the
+    /// lifetime of any reader.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, OpenError> {
+        Self::parse_len_reader(Cursor::new(data))
+    }
 }
@@ -1,11 +1,12 @@
 use std::{
     ffi::OsStr,
-    io::{Cursor, Read as _},
-    path::Path,
+    fs::File,
+    io::{BufRead as _, BufReader, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
     result::Result,
 };
 
-use backhand::{FilesystemReader, InnerNode};
+use backhand::{FilesystemReader, InnerNode, Node, SquashfsFileReader};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -20,30 +21,273 @@ pub enum Error {
     NoFilesFound,
     #[error("SquashFS file system did not contain a single .spk file as expected")]
     SPKFileNotFound,
+    #[error("No file within the SquashFS file system was selected")]
+    NoFileSelected,
+    #[error("{0}-endian SquashFS file systems are not supported")]
+    UnsupportedEndian(binrw::Endian),
+    #[error("Split update part {} does not exist", .0.display())]
+    MissingFirstPart(PathBuf),
 }
 
-pub(crate) fn extract_spk_file(path: &Path) -> Result<Vec<u8>, Error> {
-    let pattern = format!("{}.*", path.with_extension("").to_str().unwrap());
+// The SquashFS magic number as stored by a big-endian file system. Little-endian
+// file systems store it as "hsqs".
+const BIG_ENDIAN_MAGIC: &[u8] = b"sqsh";
+
+/// A file within the SquashFS file system of a split update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquashEntry {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+// A part of a split update, and the offset at which its contents start within
+// the update as a whole.
+#[derive(Debug)]
+struct Part {
+    file: File,
+    start: u64,
+    len: u64,
+}
+
+/// A reader over the numbered parts of a split update, `.000`, `.001`, and so
+/// on, that presents them as one contiguous stream.
+#[derive(Debug)]
+pub struct SplitReader {
+    parts: Vec<Part>,
+    position: u64,
+    len: u64,
+}
+
+impl SplitReader {
+    /// Opens the split update whose first part is at `path`.
+    ///
+    /// Parts are numbered consecutively from `.000`, and the first missing
+    /// number ends the update, so stray files such as a `.005` following a
+    /// `.003` are ignored.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let stem = path.with_extension("");
+        let pattern = format!(
+            "{}.[0-9][0-9][0-9]",
+            glob::Pattern::escape(&stem.to_string_lossy())
+        );
+
+        let mut paths: Vec<_> = glob::glob(&pattern)?.filter_map(Result::ok).collect();
+        paths.sort();
+
+        let mut parts = Vec::new();
+        let mut len = 0;
+        for (number, part_path) in paths.into_iter().enumerate() {
+            if part_path != part_path_for(&stem, number) {
+                break;
+            }
+            let file = File::open(&part_path)?;
+            let part_len = file.metadata()?.len();
+            parts.push(Part {
+                file,
+                start: len,
+                len: part_len,
+            });
+            len += part_len;
+        }
+
+        if parts.is_empty() {
+            return Err(Error::MissingFirstPart(part_path_for(&stem, 0)));
+        }
+
+        Ok(Self {
+            parts,
+            position: 0,
+            len,
+        })
+    }
+
+    /// Returns the combined length of all of the parts.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// The path of part `number` of the split update at `stem`. The number is
+// appended rather than substituted for an extension, as stems such as
+// `name.spk.002` already end in one.
+fn part_path_for(stem: &Path, number: usize) -> PathBuf {
+    let mut path = stem.as_os_str().to_owned();
+    path.push(format!(".{number:03}"));
+    PathBuf::from(path)
+}
+
+impl Read for SplitReader {
+    #[allow(clippy::cast_possible_truncation)]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Find the part containing the current position, skipping empty parts.
+        let index = self
+            .parts
+            .partition_point(|part| part.start + part.len <= self.position);
+        let Some(part) = self.parts.get_mut(index) else {
+            return Ok(0);
+        };
+
+        let offset = self.position - part.start;
+        let remaining = (part.len - offset).min(buf.len() as u64) as usize;
+        part.file.seek(SeekFrom::Start(offset))?;
+        let read = part.file.read(&mut buf[..remaining])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
 
-    let mut paths: Vec<_> = glob::glob(&pattern)?.filter_map(Result::ok).collect();
-    paths.sort();
+impl Seek for SplitReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+// Opens the SquashFS image of the split update whose first part is at `path`
+// without reading it into memory.
+fn open_image(path: &Path) -> Result<BufReader<SplitReader>, Error> {
+    let mut reader = BufReader::new(SplitReader::open(path)?);
+    if reader.fill_buf()?.starts_with(BIG_ENDIAN_MAGIC) {
+        return Err(Error::UnsupportedEndian(binrw::Endian::Big));
+    }
+    Ok(reader)
+}
 
+fn read_image(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut reader = SplitReader::open(path)?;
     let mut buffer: Vec<u8> = Vec::new();
-    for path in paths {
-        buffer.extend(std::fs::read(path)?);
+    reader.read_to_end(&mut buffer)?;
+
+    // Only little-endian file systems can be read, so reject big-endian ones
+    // rather than misinterpreting their contents.
+    if buffer.starts_with(BIG_ENDIAN_MAGIC) {
+        return Err(Error::UnsupportedEndian(binrw::Endian::Big));
     }
-    let mut reader = Cursor::new(&*buffer);
 
-    let filesystem = FilesystemReader::from_reader(&mut reader)?;
-    let Some(
-        spk_file_node @ backhand::Node {
-            inner: InnerNode::File(spk_file, ..),
-            ..
-        },
-    ) = filesystem
+    Ok(buffer)
+}
+
+/// Lists the files within the SquashFS file system of the split update whose
+/// first part is at `path`.
+pub fn entries(path: &Path) -> Result<Vec<SquashEntry>, Error> {
+    let buffer = read_image(path)?;
+    let filesystem = FilesystemReader::from_reader(Cursor::new(&*buffer))?;
+    Ok(filesystem
         .files()
-        .find(|n| matches!(n.inner, backhand::InnerNode::File(_)))
-    else {
+        .filter_map(|node| match &node.inner {
+            InnerNode::File(file) => Some(SquashEntry {
+                path: node.fullpath.clone(),
+                size: file.file_len() as u64,
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+pub(crate) fn extract_spk_file(path: &Path) -> Result<Vec<u8>, Error> {
+    extract_spk_file_with(path, None)
+}
+
+/// Extracts the embedded .spk file from the split update whose first part is at
+/// `path`.
+///
+/// With a `selector`, the first file it selects is extracted. Otherwise the
+/// first file in the file system is extracted, provided it is a .spk file.
+pub(crate) fn extract_spk_file_with(
+    path: &Path,
+    selector: Option<&dyn Fn(&SquashEntry) -> bool>,
+) -> Result<Vec<u8>, Error> {
+    let buffer = read_image(path)?;
+    let mut spk_file_contents = vec![];
+    copy_spk_file(Cursor::new(&*buffer), selector, &mut spk_file_contents)?;
+    Ok(spk_file_contents)
+}
+
+/// Extracts the embedded .spk file from the split update whose first part is at
+/// `path` to an anonymous temporary file, returning it rewound to its start.
+///
+/// Unlike extracting it to memory, neither the SquashFS image nor the .spk
+/// file is held in memory, so this suits updates too large to buffer. The
+/// temporary file is deleted once it is closed. `selector` chooses the file
+/// to extract as it does when opening the update with
+/// [`SPKFile::open_split_squashed_with`](crate::SPKFile::open_split_squashed_with).
+pub fn spk_file_reader(
+    path: &Path,
+    selector: Option<&dyn Fn(&SquashEntry) -> bool>,
+) -> Result<File, Error> {
+    let mut spk_file = tempfile::tempfile()?;
+    copy_spk_file(open_image(path)?, selector, &mut spk_file)?;
+    spk_file.rewind()?;
+    Ok(spk_file)
+}
+
+/// Metadata describing the SquashFS file system of a split update, as returned
+/// by [`archive_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquashInfo {
+    /// The compression used for the file system's data and metadata.
+    pub compressor: backhand::compression::Compressor,
+    /// The size of a data block in bytes.
+    pub block_size: u32,
+    /// The embedded .spk file, chosen as it is when opening the update with
+    /// [`SPKFile::open_split_squashed`](crate::SPKFile::open_split_squashed).
+    pub spk_entry: SquashEntry,
+}
+
+/// Describes the SquashFS file system of the split update whose first part is
+/// at `path`, without extracting the embedded .spk file.
+pub fn archive_info(path: &Path) -> Result<SquashInfo, Error> {
+    let filesystem = FilesystemReader::from_reader(open_image(path)?)?;
+    let (spk_file_node, spk_file) = select_spk_file(&filesystem, None)?;
+    Ok(SquashInfo {
+        compressor: filesystem.compressor,
+        block_size: filesystem.block_size,
+        spk_entry: SquashEntry {
+            path: spk_file_node.fullpath.clone(),
+            size: spk_file.file_len() as u64,
+        },
+    })
+}
+
+// Chooses the embedded .spk file within `filesystem` as described by
+// `extract_spk_file_with`.
+fn select_spk_file<'a>(
+    filesystem: &'a FilesystemReader,
+    selector: Option<&dyn Fn(&SquashEntry) -> bool>,
+) -> Result<(&'a Node<SquashfsFileReader>, &'a SquashfsFileReader), Error> {
+    let mut files = filesystem.files().filter_map(|node| match &node.inner {
+        InnerNode::File(file) => Some((node, file)),
+        _ => None,
+    });
+
+    if let Some(selector) = selector {
+        return files
+            .find(|(node, file)| {
+                selector(&SquashEntry {
+                    path: node.fullpath.clone(),
+                    size: file.file_len() as u64,
+                })
+            })
+            .ok_or(Error::NoFileSelected);
+    }
+
+    let Some((spk_file_node, spk_file)) = files.next() else {
         return Err(Error::NoFilesFound)?;
     };
 
@@ -53,11 +297,44 @@ pub(crate) fn extract_spk_file(path: &Path) -> Result<Vec<u8>, Error> {
     else {
         return Err(Error::SPKFileNotFound)?;
     };
+    Ok((spk_file_node, spk_file))
+}
+
+// Copies the embedded .spk file from the SquashFS image read by `reader` to
+// `out`, choosing it as described by `extract_spk_file_with`.
+fn copy_spk_file<'b>(
+    reader: impl backhand::BufReadSeek + 'b,
+    selector: Option<&dyn Fn(&SquashEntry) -> bool>,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    let filesystem = FilesystemReader::from_reader(reader)?;
+    let (_, spk_file) = select_spk_file(&filesystem, selector)?;
 
     let mut spk_file_reader = filesystem.file(spk_file).reader();
-    let mut spk_file_contents = vec![];
-    spk_file_contents.reserve_exact(spk_file.file_len());
-    spk_file_reader.read_to_end(&mut spk_file_contents)?;
+    std::io::copy(&mut spk_file_reader, out)?;
 
-    Ok(spk_file_contents)
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use super::SplitReader;
+
+    // Split updates are named `<name>.spk.NNN.000`, `<name>.spk.NNN.001`, and
+    // so on.
+    #[test]
+    fn split_reader_joins_numbered_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let part = |number: u32| dir.path().join(format!("game-1_0_0.spk.002.{number:03}"));
+        std::fs::write(part(0), "first ").unwrap();
+        std::fs::write(part(1), "second").unwrap();
+        std::fs::write(part(3), "stray").unwrap();
+
+        let mut reader = SplitReader::open(&part(0)).unwrap();
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "first second");
+    }
 }
@@ -0,0 +1,278 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use binrw::{BinRead as _, PosValue};
+
+use crate::{
+    chunks,
+    spk::{self, OpenError},
+};
+
+// A reader over a buffered part of a stream that reports positions within the
+// stream as a whole, starting from `base`.
+struct Shifted<R> {
+    inner: R,
+    base: u64,
+}
+
+impl<R: Read> Read for Shifted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for Shifted<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => {
+                SeekFrom::Start(offset.checked_sub(self.base).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "Seek to before the buffered data",
+                    )
+                })?)
+            }
+            pos => pos,
+        };
+        Ok(self.inner.seek(pos)? + self.base)
+    }
+}
+
+/// The files of an archive read from a stream that can't seek, as returned by
+/// [`SPKFile::parse_streaming`](spk::SPKFile::parse_streaming).
+///
+/// Each package's chunks up to its file data are buffered and parsed when the
+/// package is reached, and then its files are yielded along with their stored
+/// contents in the order their data appears in the stream. Iteration ends
+/// after the first error.
+pub struct StreamingFiles<R> {
+    reader: R,
+    // The number of bytes read from `reader`.
+    position: u64,
+    chunk_count: u32,
+    // The index of the next package to read.
+    next_package: usize,
+    package: Option<spk::Package>,
+    // The files of `package` yet to be read, sorted by their data offset.
+    pending: std::vec::IntoIter<spk::FileInfo>,
+    // The offset within the package's SDAT data that has been read up to.
+    data_position: u64,
+    // Where the current package's SPK0 chunk ends.
+    package_end: u64,
+    done: bool,
+}
+
+impl<R> std::fmt::Debug for StreamingFiles<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamingFiles")
+            .field("position", &self.position)
+            .field("chunk_count", &self.chunk_count)
+            .field("next_package", &self.next_package)
+            .field("package", &self.package)
+            .field("pending", &self.pending)
+            .field("data_position", &self.data_position)
+            .field("package_end", &self.package_end)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Read> StreamingFiles<R> {
+    fn new(reader: R) -> Result<Self, OpenError> {
+        let mut files = Self {
+            reader,
+            position: 0,
+            chunk_count: 0,
+            next_package: 0,
+            package: None,
+            pending: Vec::new().into_iter(),
+            data_position: 0,
+            package_end: 0,
+            done: false,
+        };
+
+        let (mut header, _) = files.read_chunk_header()?;
+        header.extend(files.read_bytes(4)?);
+        let spks = spk::read_chunk(
+            &mut Shifted {
+                inner: Cursor::new(header),
+                base: 0,
+            },
+            None,
+            "SPKS",
+            PosValue::<chunks::SPKS>::read_le,
+        )?;
+        files.chunk_count = spks.chunk_count;
+
+        Ok(files)
+    }
+
+    /// Returns the package that the most recently returned file belongs to.
+    #[must_use]
+    pub fn package(&self) -> Option<&spk::Package> {
+        self.package.as_ref()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn read_bytes(&mut self, len: u64) -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut buf)?;
+        self.position += buf.len() as u64;
+        if (buf.len() as u64) < len {
+            Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        }
+        Ok(buf)
+    }
+
+    fn skip_bytes(&mut self, len: u64) -> std::io::Result<()> {
+        let skipped = std::io::copy(&mut (&mut self.reader).take(len), &mut std::io::sink())?;
+        self.position += skipped;
+        if skipped < len {
+            Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        }
+        Ok(())
+    }
+
+    // Reads a chunk header, returning its raw bytes and the chunk's byte
+    // length.
+    fn read_chunk_header(&mut self) -> std::io::Result<(Vec<u8>, u64)> {
+        let mut header = self.read_bytes(8)?;
+        // A 32-bit length of 0xffffffff indicates that a 64-bit length follows.
+        let byte_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if byte_len != u32::MAX {
+            return Ok((header, u64::from(byte_len)));
+        }
+
+        let byte_len = self.read_bytes(8)?;
+        header.extend_from_slice(&byte_len);
+        let byte_len = u64::from_le_bytes(byte_len.try_into().unwrap());
+        Ok((header, byte_len))
+    }
+
+    fn read_package(&mut self) -> Result<(), OpenError> {
+        let index = self.next_package;
+        self.next_package += 1;
+
+        let spk0_offset = self.position;
+        let (header, _) = self.read_chunk_header()?;
+        let spk0 = spk::read_chunk(
+            &mut Shifted {
+                inner: Cursor::new(header),
+                base: spk0_offset,
+            },
+            Some(index),
+            "SPK0",
+            PosValue::<chunks::SPK0>::read_le,
+        )?;
+        self.package_end = spk0.pos + spk0.offset_to_next();
+
+        // Buffer every chunk up to and including the FEND chunk, followed by
+        // the SDAT chunk's header.
+        let base = self.position;
+        let mut buffer = Vec::new();
+        loop {
+            let offset = self.position;
+            if offset >= self.package_end {
+                Err(OpenError::UnexpectedChunk { offset })?;
+            }
+            let (header, byte_len) = self.read_chunk_header()?;
+            let is_fend = header.starts_with(b"FEND");
+            buffer.extend(header);
+            buffer.extend(self.read_bytes(byte_len)?);
+            if is_fend {
+                break;
+            }
+        }
+        buffer.extend(self.read_chunk_header()?.0);
+
+        let package = spk::parse_package(
+            &mut Shifted {
+                inner: Cursor::new(buffer),
+                base,
+            },
+            index,
+            &spk0,
+            &mut spk::ParseOptions::default(),
+        )?
+        .expect("packages aren't filtered");
+
+        let mut files = package.files.clone();
+        files.sort_by_key(|file_info| file_info.stored_offset);
+        self.pending = files.into_iter();
+        self.data_position = 0;
+        self.package = Some(package);
+        Ok(())
+    }
+
+    fn next_file(&mut self) -> Option<Result<(spk::FileInfo, Vec<u8>), OpenError>> {
+        loop {
+            if let Some(file_info) = self.pending.next() {
+                return Some(self.read_file(file_info));
+            }
+
+            if self.package.is_some() {
+                // Skip whatever follows the last file's data.
+                let Some(remaining) = self.package_end.checked_sub(self.position) else {
+                    return Some(Err(OpenError::UnexpectedChunk {
+                        offset: self.package_end,
+                    }));
+                };
+                if let Err(error) = self.skip_bytes(remaining) {
+                    return Some(Err(error.into()));
+                }
+            }
+            if self.next_package >= self.chunk_count as usize {
+                return None;
+            }
+            if let Err(error) = self.read_package() {
+                return Some(Err(error));
+            }
+        }
+    }
+
+    fn read_file(
+        &mut self,
+        file_info: spk::FileInfo,
+    ) -> Result<(spk::FileInfo, Vec<u8>), OpenError> {
+        // Data that has already been passed can't be read again.
+        if file_info.stored_offset < self.data_position {
+            Err(OpenError::OverlappingData(file_info.name.clone()))?;
+        }
+
+        self.skip_bytes(file_info.stored_offset - self.data_position)?;
+        let contents = self.read_bytes(file_info.data_size)?;
+        self.data_position = file_info.stored_offset + file_info.data_size;
+        Ok((file_info, contents))
+    }
+}
+
+impl<R: Read> Iterator for StreamingFiles<R> {
+    type Item = Result<(spk::FileInfo, Vec<u8>), OpenError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = self.next_file();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+impl spk::SPKFile<'_> {
+    /// Parses an archive from a stream that can't seek, such as a pipe or a
+    /// socket, returning its files and their stored contents in the order
+    /// they appear in the stream.
+    ///
+    /// This reads the archive's `SPKS` chunk header before returning, and the
+    /// rest of the archive as the files are iterated over. Only the chunks
+    /// describing the current package and the contents of the current file
+    /// are held in memory. Unlike [`SPKFile::parse`](spk::SPKFile::parse), this
+    /// doesn't support random access, and fails with
+    /// [`OpenError::OverlappingData`] on archives whose files share data.
+    pub fn parse_streaming<R: Read>(reader: R) -> Result<StreamingFiles<R>, OpenError> {
+        StreamingFiles::new(reader)
+    }
+}
@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use crate::spk;
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    // Only files have a size. Directories are implied by the files within them.
+    size: Option<u64>,
+}
+
+fn render(out: &mut String, children: &BTreeMap<String, Node>, indent: &str) {
+    for (i, (name, node)) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        out.push_str(indent);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(name);
+        if let Some(size) = node.size {
+            let _ = write!(out, " ({size} bytes)");
+        }
+        out.push('\n');
+
+        let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+        render(out, &node.children, &indent);
+    }
+}
+
+impl spk::SPKFile<'_> {
+    /// Renders the contents of the archive as a tree, in the style of the
+    /// `tree` command.
+    ///
+    /// Each package is a top-level node labelled with the prefix its files are
+    /// installed under, with its files nested beneath it by path and annotated
+    /// with their sizes. Entries are sorted by name so the output is stable.
+    #[must_use]
+    pub fn tree_string(&self) -> String {
+        let mut out = String::new();
+        for package in &self.packages {
+            let mut root = Node::default();
+            for file_info in &package.files {
+                let mut node = &mut root;
+                for component in file_info.name.split('/').filter(|c| !c.is_empty()) {
+                    node = node.children.entry(component.to_string()).or_default();
+                }
+                node.size = Some(file_info.size);
+            }
+
+            let _ = writeln!(out, "{} ({})", package.name, package.type_.path_prefix());
+            render(&mut out, &root.children, "");
+        }
+        out
+    }
+
+    /// Writes a listing of the archive's contents to `w`, in the style of
+    /// `tar -tv`.
+    ///
+    /// Each package is introduced by a line giving its name, version, type,
+    /// and game ID, followed by a line for each of its files giving its mode,
+    /// size, and installed path, in the order they are stored.
+    pub fn print_contents(&self, w: &mut dyn std::io::Write) -> std::io::Result<()> {
+        for (i, package) in self.packages.iter().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            writeln!(
+                w,
+                "{} {} ({}, game ID {})",
+                package.name,
+                package.version,
+                package.type_,
+                package.game_id.as_deref().unwrap_or("none")
+            )?;
+
+            let width = package
+                .files
+                .iter()
+                .map(|file_info| file_info.size.to_string().len())
+                .max()
+                .unwrap_or_default();
+            for file_info in &package.files {
+                writeln!(
+                    w,
+                    "{} {:>width$} {}",
+                    file_info.mode_string(),
+                    file_info.size,
+                    file_info.full_path(package)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::Context as _;
 use hmac::{self, Mac as _};
 use md5::Digest;
-use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use rayon::iter::{
+    IndexedParallelIterator as _, IntoParallelRefIterator as _, ParallelIterator as _,
+};
 use sha1;
+use thiserror::Error;
 
 use crate::spk;
 
@@ -12,19 +15,46 @@ struct VerificationResult {
     hmac: bool,
 }
 
+/// The ways a file within an archive can fail an integrity check.
+#[derive(Error, Debug)]
+pub enum IntegrityError {
+    #[error("MD5 does not match")]
+    Md5Mismatch,
+    #[error("HMAC does not match")]
+    HmacMismatch,
+    #[error("Neither MD5 nor HMAC match")]
+    Md5AndHmacMismatch,
+    #[error("Failed to read file: {0}")]
+    Read(#[from] spk::ReadError),
+}
+
+type HmacSha1 = hmac::Hmac<sha1::Sha1>;
+
+fn new_hmac_sha1(key: &[u8]) -> HmacSha1 {
+    HmacSha1::new_from_slice(key).expect("HMAC-SHA1 accepts keys of any length")
+}
+
+pub(crate) fn hmac_sha1(key: &[u8], contents: &[u8]) -> [u8; 20] {
+    let mut sha1_hmac = new_hmac_sha1(key);
+    sha1_hmac.update(contents);
+    sha1_hmac.finalize().into_bytes().into()
+}
+
+fn verify_contents(file_info: &spk::FileInfo, contents: &[u8]) -> bool {
+    md5::Md5::digest(contents) == file_info.md5.into()
+        && hmac_sha1(spk::HMAC_KEY, contents) == file_info.hmac
+}
+
 fn verify_one_file(
     file: &spk::SPKFile,
     file_info: &spk::FileInfo,
-) -> anyhow::Result<VerificationResult> {
+) -> Result<VerificationResult, spk::ReadError> {
     let contents = file.read(file_info)?;
 
     let md5_digest = md5::Md5::digest(&contents);
     let md5_result = md5_digest == file_info.md5.into();
 
-    let mut sha1_hmac = hmac::Hmac::<sha1::Sha1>::new_from_slice(spk::HMAC_KEY)?;
-    sha1_hmac.update(&contents);
-    let sha1_hmac_digest = sha1_hmac.finalize().into_bytes();
-    let sha1_hmac_result = sha1_hmac_digest == file_info.hmac.into();
+    let sha1_hmac_result = hmac_sha1(spk::HMAC_KEY, &contents) == file_info.hmac;
 
     Ok(VerificationResult {
         md5: md5_result,
@@ -32,44 +62,29 @@ fn verify_one_file(
     })
 }
 
+// Verifies every file as `SPKFile::verify_all` does, failing with the names of
+// the files that don't match, or with the first file that can't be read.
 pub(crate) fn verify_all(file: &spk::SPKFile) -> anyhow::Result<()> {
-    // Verify files from all packages in parallel, collecting only the failures.
-    let failures = file
-        .packages
-        .par_iter()
-        .map(|package| {
-            package
-                .files
-                .par_iter()
-                .map(|file_info| -> anyhow::Result<_> {
-                    let result = verify_one_file(file, file_info).with_context(|| {
-                        format!(
-                            "Error attempting to verify file {} in package {}",
-                            file_info.name, package.name
-                        )
-                    })?;
-                    Ok((file_info, result.md5 && result.hmac))
-                })
-        })
-        .flatten()
-        .filter(|result| match result {
-            Ok((_, result)) => !result,
-            Err(_) => true,
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let mut failures = Vec::new();
+    for (package_index, file_index, error) in file.verify_all() {
+        let package = &file.packages[package_index];
+        let file_info = &package.files[file_index];
+        if let IntegrityError::Read(error) = error {
+            return Err(error).with_context(|| {
+                format!(
+                    "Error attempting to verify file {} in package {}",
+                    file_info.name, package.name
+                )
+            });
+        }
+        failures.push(file_info.name.clone());
+    }
 
     if failures.is_empty() {
         return Ok(());
     }
 
-    anyhow::bail!(
-        "Some files failed verification: {}",
-        failures
-            .iter()
-            .map(|(file_info, _)| file_info.name.clone())
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
+    anyhow::bail!("Some files failed verification: {}", failures.join(", "));
 }
 
 fn check(value: bool) -> &'static str {
@@ -83,10 +98,7 @@ pub fn verify(file: &mut spk::SPKFile) -> anyhow::Result<()> {
         }
 
         println!("Package: {}", package.name);
-        println!(
-            "Version: {}.{}.{}",
-            package.version.0, package.version.1, package.version.2
-        );
+        println!("Version: {}", package.version);
 
         let mut results = package
             .files
@@ -101,7 +113,7 @@ pub fn verify(file: &mut spk::SPKFile) -> anyhow::Result<()> {
         for (file_info, result) in results {
             println!(
                 "{:165} mode={:o} size={:11}  md5: {}  hmac: {}  ",
-                format!("{}{}", package.type_.path_prefix(), file_info.name),
+                file_info.full_path(package),
                 file_info.mode,
                 file_info.size,
                 check(result.md5),
@@ -112,3 +124,247 @@ pub fn verify(file: &mut spk::SPKFile) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// How a file's stored data offset was interpreted when reading its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetBase {
+    /// Relative to the start of the package's `SDAT` data, as is normal.
+    SdatRelative,
+    /// Relative to the start of the archive.
+    Absolute,
+}
+
+/// A reader over the contents of a file within an archive that checks the
+/// file's MD5 and HMAC as its data is consumed.
+///
+/// Digest mismatches are reported at the end of the data: the read that would
+/// otherwise return 0 fails with an [`std::io::ErrorKind::InvalidData`] error.
+pub struct VerifyingReader<'s, 'a> {
+    file: &'s spk::SPKFile<'a>,
+    file_info: &'s spk::FileInfo,
+    position: u64,
+    // Taken once the end of the data is reached and the digests are checked.
+    digests: Option<(md5::Md5, HmacSha1)>,
+}
+
+impl std::io::Read for VerifyingReader<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.file_info.data_size - self.position;
+        if remaining == 0 {
+            if let Some((md5, sha1_hmac)) = self.digests.take() {
+                let md5_result = md5.finalize() == self.file_info.md5.into();
+                let sha1_hmac_result =
+                    sha1_hmac.finalize().into_bytes() == self.file_info.hmac.into();
+                if !md5_result || !sha1_hmac_result {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("File {} failed verification", self.file_info.name),
+                    ));
+                }
+            }
+            return Ok(0);
+        }
+
+        let len = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let buf = &mut buf[..len];
+        self.file
+            .read_exact_at(self.file_info.offset + self.position, buf)
+            .map_err(std::io::Error::other)?;
+        self.position += len as u64;
+
+        if let Some((md5, sha1_hmac)) = &mut self.digests {
+            md5.update(&*buf);
+            sha1_hmac.update(buf);
+        }
+        Ok(len)
+    }
+}
+
+impl<'a> spk::SPKFile<'a> {
+    /// Returns a reader over the contents of `file_info` that verifies the
+    /// file's MD5 and HMAC once all of its data has been read.
+    ///
+    /// This avoids buffering the whole file or making a separate verification
+    /// pass. See [`VerifyingReader`] for how mismatches are reported.
+    pub fn open_file_verified<'s>(
+        &'s self,
+        file_info: &'s spk::FileInfo,
+    ) -> Result<VerifyingReader<'s, 'a>, spk::ReadError> {
+        Ok(VerifyingReader {
+            file: self,
+            file_info,
+            position: 0,
+            digests: Some((md5::Md5::new(), new_hmac_sha1(spk::HMAC_KEY))),
+        })
+    }
+
+    /// Reads the contents of `file_info` and checks them against its MD5,
+    /// failing with [`spk::ReadError::ChecksumMismatch`] if they don't match.
+    ///
+    /// This is [`SPKFile::read`](spk::SPKFile::read) with an integrity check,
+    /// at the cost of hashing the file's contents.
+    pub fn read_verified(&self, file_info: &spk::FileInfo) -> Result<Vec<u8>, spk::ReadError> {
+        let contents = self.read(file_info)?;
+        let actual: [u8; 16] = md5::Md5::digest(&contents).into();
+        if actual != file_info.md5 {
+            Err(spk::ReadError::ChecksumMismatch {
+                expected: file_info.md5,
+                actual,
+            })?;
+        }
+        Ok(contents)
+    }
+
+    /// Checks the stored contents of `file_info` against its HMAC.
+    ///
+    /// The HMAC is an HMAC-SHA1 keyed with the key all archives are signed
+    /// with, so this detects files that were modified after signing.
+    pub fn verify_hmac(&self, file_info: &spk::FileInfo) -> Result<bool, spk::ReadError> {
+        let contents = self.read(file_info)?;
+        Ok(hmac_sha1(spk::HMAC_KEY, &contents) == file_info.hmac)
+    }
+
+    /// Reads the contents of `file_info` and checks its MD5 and HMAC, failing
+    /// with [`spk::ReadError::VerificationFailed`] if they don't match.
+    ///
+    /// With `retry_alternate_offset`, a file that fails to verify is read again
+    /// treating its stored offset as relative to the start of the archive
+    /// rather than to the package's `SDAT` data. This helps diagnose archives
+    /// written with a different offset convention, at the cost of a second
+    /// read for files that are genuinely corrupt. The returned [`OffsetBase`]
+    /// reports which interpretation verified.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_verified_retrying(
+        &self,
+        file_info: &spk::FileInfo,
+        retry_alternate_offset: bool,
+    ) -> Result<(Vec<u8>, OffsetBase), spk::ReadError> {
+        let contents = self.read(file_info)?;
+        if verify_contents(file_info, &contents) {
+            return Ok((contents, OffsetBase::SdatRelative));
+        }
+
+        if retry_alternate_offset {
+            let mut contents = vec![0; file_info.data_size as usize];
+            match self.read_exact_at(file_info.stored_offset, &mut contents) {
+                Ok(()) => {
+                    if verify_contents(file_info, &contents) {
+                        return Ok((contents, OffsetBase::Absolute));
+                    }
+                }
+                // Data that would run past the end of the archive can't be the
+                // file's contents either.
+                Err(spk::ReadError::OutOfBounds { .. }) => {}
+                Err(spk::ReadError::IOError(error))
+                    if error.kind() == std::io::ErrorKind::UnexpectedEof => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(spk::ReadError::VerificationFailed)
+    }
+
+    /// Checks the MD5 and HMAC of every file in every package, returning the
+    /// package index, file index, and error of each file that fails.
+    ///
+    /// Unlike verifying files one at a time with `?`, this carries on past
+    /// failures so that a complete report can be produced. Files that can't be
+    /// read are reported with [`IntegrityError::Read`].
+    #[must_use]
+    pub fn verify_all(&self) -> Vec<(usize, usize, IntegrityError)> {
+        self.packages
+            .par_iter()
+            .enumerate()
+            .flat_map(|(package_index, package)| {
+                package
+                    .files
+                    .par_iter()
+                    .enumerate()
+                    .filter_map(move |(file_index, file_info)| {
+                        let error = match verify_one_file(self, file_info) {
+                            Ok(result) => match (result.md5, result.hmac) {
+                                (true, true) => return None,
+                                (false, true) => IntegrityError::Md5Mismatch,
+                                (true, false) => IntegrityError::HmacMismatch,
+                                (false, false) => IntegrityError::Md5AndHmacMismatch,
+                            },
+                            Err(error) => error.into(),
+                        };
+                        Some((package_index, file_index, error))
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the number of bytes that must be read and hashed to verify every
+    /// file in the archive, for reporting the progress of a verification pass.
+    #[must_use]
+    pub fn verify_bytes(&self) -> u64 {
+        self.packages
+            .iter()
+            .flat_map(|package| &package.files)
+            .map(|file_info| file_info.data_size)
+            .sum()
+    }
+
+    /// Checks whether `key` is the HMAC key the archive's files were signed with.
+    ///
+    /// Only the smallest non-empty file is read and checked. Returns `false` if
+    /// the archive has no non-empty files to check the key against.
+    pub fn check_hmac_key(&self, key: &[u8]) -> Result<bool, spk::ReadError> {
+        let Some(file_info) = self
+            .packages
+            .iter()
+            .flat_map(|package| &package.files)
+            .filter(|file_info| file_info.data_size > 0)
+            .min_by_key(|file_info| file_info.data_size)
+        else {
+            return Ok(false);
+        };
+
+        let contents = self.read(file_info)?;
+        Ok(hmac_sha1(key, &contents) == file_info.hmac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        SPKWriter, chunks, spk,
+        writer::{NewFile, NewPackage},
+    };
+
+    #[test]
+    fn verify_hmac_detects_corrupted_data() {
+        let file = |name: &str| NewFile {
+            name: name.into(),
+            size: 8,
+            mode: 0o100_644,
+            data: b"contents".to_vec(),
+        };
+        let mut writer = SPKWriter::new();
+        writer.add_package(NewPackage {
+            name: "test".to_string(),
+            package_id: [0; 3],
+            version: spk::Version::default(),
+            type_: chunks::PackageType::Spike2,
+            uncompressed_size: None,
+            sidx_trailer: [0; 12],
+            files: vec![file("intact"), file("corrupted")],
+        });
+        let mut archive = Vec::new();
+        writer.write(&mut archive).unwrap();
+
+        // The last file's data ends the archive.
+        *archive.last_mut().unwrap() ^= 0xff;
+
+        let spk = spk::SPKFile::parse(Cursor::new(archive)).unwrap();
+        let files = &spk.packages[0].files;
+        assert!(matches!(spk.verify_hmac(&files[0]), Ok(true)));
+        assert!(matches!(spk.verify_hmac(&files[1]), Ok(false)));
+    }
+}
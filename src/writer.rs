@@ -0,0 +1,318 @@
+use std::path::{Path, PathBuf};
+
+use md5::Digest as _;
+use thiserror::Error;
+
+use crate::{chunks, spk, verify};
+
+#[derive(Error, Debug)]
+pub enum WriteError {
+    #[error("Failed to write file: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to read file from archive: {0}")]
+    Read(#[from] spk::ReadError),
+    #[error("Package name is longer than 28 bytes: {0}")]
+    PackageNameTooLong(String),
+}
+
+#[derive(Error, Debug)]
+pub enum BuildError {
+    #[error("Failed to read file: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to name package after directory {}", .0.display())]
+    NoPackageName(PathBuf),
+}
+
+// The size of the SIDX package name field, which must include a NUL terminator.
+const PACKAGE_NAME_LEN: usize = 0x1d;
+
+/// A file to be written by [`SPKWriter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewFile {
+    /// The file's name, as it is to be stored.
+    pub name: Vec<u8>,
+    /// The file's size once installed. This differs from the length of `data`
+    /// if `data` is compressed.
+    pub size: u64,
+    pub mode: u16,
+    /// The file's contents, as they are to be stored.
+    pub data: Vec<u8>,
+}
+
+/// A package to be written by [`SPKWriter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewPackage {
+    pub name: String,
+    /// The three-character game ID, or all NULs for none.
+    pub package_id: [u8; 3],
+    pub version: spk::Version,
+    pub type_: chunks::PackageType,
+    /// The value of the package's `SZ64` chunk, which is omitted if `None`.
+    pub uncompressed_size: Option<u64>,
+    /// The last 12 bytes of the package's `SIDX` chunk, whose purpose is
+    /// unknown.
+    pub sidx_trailer: [u8; 12],
+    pub files: Vec<NewFile>,
+}
+
+impl NewPackage {
+    /// Copies `package` and the contents of its files from `spk`, so that it
+    /// can be written out again, possibly after being modified.
+    pub fn from_package(package: &spk::Package, spk: &spk::SPKFile) -> Result<Self, WriteError> {
+        Ok(Self {
+            name: package.name.clone(),
+            package_id: package.package_id,
+            version: package.version,
+            type_: package.type_,
+            uncompressed_size: package.uncompressed_size,
+            sidx_trailer: package.raw_sidx_trailer,
+            files: package
+                .files
+                .iter()
+                .map(|file_info| -> Result<_, WriteError> {
+                    Ok(NewFile {
+                        name: file_info.raw_name.clone(),
+                        size: file_info.size,
+                        mode: file_info.mode,
+                        data: spk.read(file_info)?,
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Writes packages out as a .spk file.
+///
+/// Each file's data offset, data size, MD5, and HMAC are computed as it is
+/// written. Each file's name is stored once in the `STRS` chunk, so names
+/// shared between files in the source archive are written out separately.
+/// Files are described by `FINF` chunks unless their sizes or offsets need
+/// `FI64` chunks, and chunk lengths use the shorter 32-bit form wherever they
+/// fit, as assumed by [`Package::serialized_size`](spk::Package::serialized_size).
+///
+/// Parsing the output yields the same packages as were written, other than
+/// the offsets describing where things are within the archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SPKWriter {
+    pub packages: Vec<NewPackage>,
+}
+
+// Appends a chunk header for a chunk with `byte_len` bytes of content.
+#[allow(clippy::cast_possible_truncation)]
+fn chunk_header(out: &mut Vec<u8>, magic: [u8; 4], byte_len: u64) {
+    out.extend_from_slice(&magic);
+    if chunks::ByteLen::smallest_header_size(byte_len) == 8 {
+        out.extend_from_slice(&(byte_len as u32).to_le_bytes());
+    } else {
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&byte_len.to_le_bytes());
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn package_chunk(package: &NewPackage) -> Result<Vec<u8>, WriteError> {
+    if package.name.len() >= PACKAGE_NAME_LEN {
+        Err(WriteError::PackageNameTooLong(package.name.clone()))?;
+    }
+
+    let mut body = Vec::new();
+
+    chunk_header(&mut body, *b"SIDX", chunks::SIDX::LEN - 8);
+    let mut name = [0; PACKAGE_NAME_LEN];
+    name[..package.name.len()].copy_from_slice(package.name.as_bytes());
+    body.extend_from_slice(&name);
+    body.extend_from_slice(&package.package_id);
+    body.extend_from_slice(&[
+        package.version.major,
+        package.version.minor,
+        package.version.patch,
+        package.type_ as u8,
+    ]);
+    body.extend_from_slice(&package.sidx_trailer);
+
+    if let Some(uncompressed_size) = package.uncompressed_size {
+        chunk_header(&mut body, *b"SZ64", chunks::SZ64::LEN - 8);
+        body.extend_from_slice(&uncompressed_size.to_le_bytes());
+    }
+
+    let mut strings = Vec::new();
+    let mut name_offsets = Vec::new();
+    for file in &package.files {
+        name_offsets.push(strings.len() as u64);
+        strings.extend_from_slice(&file.name);
+        strings.push(0);
+    }
+    // STRS chunks always have a 32-bit length.
+    body.extend_from_slice(b"STRS");
+    body.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+    body.extend_from_slice(&strings);
+
+    let mut data_offset = 0;
+    for (file, name_offset) in package.files.iter().zip(name_offsets) {
+        let data_size = file.data.len() as u64;
+        let mode_and_digests = |body: &mut Vec<u8>| {
+            body.extend_from_slice(&file.mode.to_le_bytes());
+            body.extend_from_slice(&[0; 3]);
+            body.extend_from_slice(&verify::hmac_sha1(spk::HMAC_KEY, &file.data));
+            body.extend_from_slice(&md5::Md5::digest(&file.data));
+        };
+
+        let fields = [name_offset, file.size, data_offset, data_size];
+        if let Ok(fields) = fields
+            .map(u32::try_from)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+        {
+            body.extend_from_slice(b"FINF");
+            body.extend_from_slice(&((chunks::FINF::HEADER_LEN - 8) as u32).to_le_bytes());
+            for field in fields {
+                body.extend_from_slice(&field.to_le_bytes());
+            }
+            mode_and_digests(&mut body);
+            body.extend_from_slice(&[0; 3]);
+        } else {
+            body.extend_from_slice(b"FI64");
+            body.extend_from_slice(&((chunks::FI64::HEADER_LEN - 8) as u32).to_le_bytes());
+            for field in fields {
+                body.extend_from_slice(&field.to_le_bytes());
+            }
+            mode_and_digests(&mut body);
+            body.extend_from_slice(&[0; 7]);
+        }
+
+        data_offset += data_size;
+    }
+
+    chunk_header(&mut body, *b"FEND", 0);
+
+    chunk_header(&mut body, *b"SDAT", data_offset);
+    for file in &package.files {
+        body.extend_from_slice(&file.data);
+    }
+
+    let mut chunk = Vec::new();
+    chunk_header(&mut chunk, *b"SPK0", body.len() as u64);
+    chunk.extend_from_slice(&body);
+    Ok(chunk)
+}
+
+// Appends the files within `dir` to `files`, named by their path relative to the
+// root directory, which `prefix` is the path of `dir` within.
+fn add_directory(dir: &Path, prefix: &[u8], files: &mut Vec<NewFile>) -> Result<(), BuildError> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+
+    for entry in entries {
+        let name = [prefix, entry.file_name().as_encoded_bytes()].concat();
+        let path = entry.path();
+        let metadata = std::fs::symlink_metadata(&path)?;
+        if metadata.is_dir() {
+            add_directory(&path, &[&name[..], b"/"].concat(), files)?;
+            continue;
+        }
+
+        let data = if metadata.is_symlink() {
+            std::fs::read_link(&path)?
+                .into_os_string()
+                .into_encoded_bytes()
+        } else {
+            std::fs::read(&path)?
+        };
+        files.push(NewFile {
+            name,
+            size: data.len() as u64,
+            mode: mode(&metadata),
+            data,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[allow(clippy::cast_possible_truncation)]
+fn mode(metadata: &std::fs::Metadata) -> u16 {
+    // The file type and permission bits all fit within the lower 16 bits.
+    std::os::unix::fs::MetadataExt::mode(metadata) as u16
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &std::fs::Metadata) -> u16 {
+    if metadata.is_symlink() {
+        0o120_777
+    } else {
+        0o100_644
+    }
+}
+
+impl SPKWriter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a writer for a single package of type `package_type` holding
+    /// the files within `root`, the inverse of extracting a package.
+    ///
+    /// The package is named after `root`, and its files are named by their
+    /// paths relative to `root` and given the modes of the files they were
+    /// read from. Symlinks are stored as links rather than followed. The
+    /// package has no game ID, a version of 0.0.0, and a zeroed `SIDX`
+    /// trailer, which can be changed before it is written.
+    pub fn from_directory(
+        root: &Path,
+        package_type: chunks::PackageType,
+    ) -> Result<Self, BuildError> {
+        let name = root
+            .file_name()
+            .ok_or_else(|| BuildError::NoPackageName(root.to_path_buf()))?
+            .to_string_lossy()
+            .into_owned();
+
+        let mut files = Vec::new();
+        add_directory(root, &[], &mut files)?;
+
+        Ok(Self {
+            packages: vec![NewPackage {
+                name,
+                package_id: [0; 3],
+                version: spk::Version::default(),
+                type_: package_type,
+                uncompressed_size: None,
+                sidx_trailer: [0; 12],
+                files,
+            }],
+        })
+    }
+
+    pub fn add_package(&mut self, package: NewPackage) -> &mut Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Writes the packages to `writer` as a .spk file.
+    ///
+    /// Each package is assembled in memory before it is written.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write(&self, mut writer: impl std::io::Write) -> Result<(), WriteError> {
+        let packages = self
+            .packages
+            .iter()
+            .map(package_chunk)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The SPKS chunk's contents are its package count followed by the packages.
+        let byte_len = 4 + packages.iter().map(|chunk| chunk.len() as u64).sum::<u64>();
+        let mut header = Vec::new();
+        chunk_header(&mut header, *b"SPKS", byte_len);
+        header.extend_from_slice(&(self.packages.len() as u32).to_le_bytes());
+
+        writer.write_all(&header)?;
+        for chunk in packages {
+            writer.write_all(&chunk)?;
+        }
+
+        Ok(())
+    }
+}